@@ -1,12 +1,15 @@
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Mutex, MutexGuard};
 use std::time::SystemTime;
 use tokio::sync::watch;
 
+use crate::project::ProjectKey;
+
 static NEXT_ID: AtomicU64 = AtomicU64::new(1);
 
 lazy_static! {
@@ -32,6 +35,18 @@ pub enum RunStatus {
     Cancelled,
 }
 
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Pending => "pending",
+            RunStatus::Running => "running",
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+            RunStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct Run {
     pub key: String,
@@ -42,6 +57,12 @@ pub struct Run {
     pub stderr_path: PathBuf,
     #[serde(skip)]
     pub pid: Option<u32>,
+    /// tmux pane running this run, for batches created with `tmux: true`.
+    #[serde(skip)]
+    pub pane: Option<String>,
+    /// `WORMHOLE_*` variables for the run's project; see `terminal::batch_env_vars`.
+    #[serde(skip)]
+    pub env: Vec<(String, String)>,
     pub started_at: Option<SystemTime>,
     pub finished_at: Option<SystemTime>,
 }
@@ -50,6 +71,10 @@ pub struct Run {
 pub struct Batch {
     pub id: String,
     pub command: Vec<String>,
+    /// Name of the `wormhole.toml` schedule that created this batch, if any.
+    pub schedule: Option<String>,
+    /// Run in a new pane of each project's tmux window rather than via `sh -c`.
+    pub tmux: bool,
     pub created_at: SystemTime,
     pub runs: Vec<Run>,
 }
@@ -70,6 +95,13 @@ impl Batch {
     pub fn is_done(&self) -> bool {
         self.completed_count() == self.runs.len()
     }
+
+    pub fn failed_count(&self) -> usize {
+        self.runs
+            .iter()
+            .filter(|r| r.status == RunStatus::Failed)
+            .count()
+    }
 }
 
 #[derive(Deserialize)]
@@ -82,6 +114,10 @@ pub struct RunSpec {
 pub struct BatchRequest {
     pub command: Vec<String>,
     pub runs: Vec<RunSpec>,
+    #[serde(default)]
+    pub schedule: Option<String>,
+    #[serde(default)]
+    pub tmux: bool,
 }
 
 pub struct Store<'a>(MutexGuard<'a, Vec<Batch>>);
@@ -106,6 +142,17 @@ impl<'a> Store<'a> {
     pub fn insert(&mut self, batch: Batch) {
         self.0.push(batch);
     }
+
+    /// Remove a completed batch and its output files. Running batches are kept.
+    pub fn remove(&mut self, id: &str) -> bool {
+        match self.0.iter().position(|b| b.id == id && b.is_done()) {
+            Some(i) => {
+                remove_output_files(&self.0.remove(i));
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 // -- API response types --
@@ -147,9 +194,13 @@ pub struct BatchListResponse {
 pub struct BatchSummary {
     pub id: String,
     pub command: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
     pub created_at: f64,
     pub total: usize,
     pub completed: usize,
+    #[serde(default)]
+    pub failed: usize,
     pub done: bool,
 }
 
@@ -176,9 +227,11 @@ impl Batch {
         BatchSummary {
             id: self.id.clone(),
             command: self.command.clone(),
+            schedule: self.schedule.clone(),
             created_at: system_time_to_epoch(self.created_at),
             total: self.runs.len(),
             completed: self.completed_count(),
+            failed: self.failed_count(),
             done: self.is_done(),
         }
     }
@@ -269,6 +322,169 @@ impl BatchResponse {
     }
 }
 
+/// Runs grouped by identical status, exit code and stdout, so a failed or
+/// unfinished run never joins a group of successes. The largest group is the
+/// baseline; every other group is an outlier. With a tie for largest there is
+/// no baseline and nothing is flagged.
+#[derive(Serialize, Deserialize)]
+pub struct OutputGroup {
+    pub status: RunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub keys: Vec<String>,
+    pub outlier: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProjectReport {
+    pub status: RunStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    pub group: usize,
+    pub outlier: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BatchReport {
+    pub id: String,
+    pub command: Vec<String>,
+    pub groups: Vec<OutputGroup>,
+    pub projects: BTreeMap<String, ProjectReport>,
+}
+
+impl BatchResponse {
+    pub fn report(&self) -> BatchReport {
+        let mut groups: Vec<OutputGroup> = Vec::new();
+        let mut sorted: Vec<&RunResponse> = self.runs.iter().collect();
+        sorted.sort_by(|a, b| a.key.cmp(&b.key));
+        for run in &sorted {
+            let stdout = run.stdout.as_deref().unwrap_or("").trim_end();
+            let same = |g: &&mut OutputGroup| {
+                g.status == run.status && g.exit_code == run.exit_code && g.stdout == stdout
+            };
+            match groups.iter_mut().find(same) {
+                Some(g) => g.keys.push(run.key.clone()),
+                None => groups.push(OutputGroup {
+                    status: run.status,
+                    exit_code: run.exit_code,
+                    stdout: stdout.to_string(),
+                    keys: vec![run.key.clone()],
+                    outlier: false,
+                }),
+            }
+        }
+        // Stable sort keeps groups of equal size in key order.
+        groups.sort_by_key(|g| std::cmp::Reverse(g.keys.len()));
+        let has_baseline = match groups.as_slice() {
+            [first, second, ..] => first.keys.len() > second.keys.len(),
+            _ => false,
+        };
+        if has_baseline {
+            for g in groups.iter_mut().skip(1) {
+                g.outlier = true;
+            }
+        }
+
+        let projects = sorted
+            .iter()
+            .map(|run| {
+                let group = groups
+                    .iter()
+                    .position(|g| g.keys.contains(&run.key))
+                    .unwrap_or(0);
+                let report = ProjectReport {
+                    status: run.status,
+                    exit_code: run.exit_code,
+                    group,
+                    outlier: groups.get(group).is_some_and(|g| g.outlier),
+                    stdout: run.stdout.clone().unwrap_or_default(),
+                    stderr: run.stderr.clone().unwrap_or_default(),
+                };
+                (run.key.clone(), report)
+            })
+            .collect();
+
+        BatchReport {
+            id: self.id.clone(),
+            command: self.command.clone(),
+            groups,
+            projects,
+        }
+    }
+}
+
+impl BatchReport {
+    /// One line per project, each a self-contained JSON object.
+    pub fn render_jsonl(&self) -> String {
+        let mut out = String::new();
+        for (key, project) in &self.projects {
+            let mut value = serde_json::to_value(project).unwrap();
+            value["key"] = serde_json::Value::String(key.clone());
+            out.push_str(&value.to_string());
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn render_terminal(&self) -> String {
+        let mut out = String::new();
+        for group in &self.groups {
+            let label = if group.outlier { " (outlier)" } else { "" };
+            let noun = if group.keys.len() == 1 {
+                "project"
+            } else {
+                "projects"
+            };
+            let status = match (group.status, group.exit_code) {
+                (RunStatus::Succeeded, _) => String::new(),
+                (RunStatus::Failed, Some(code)) => format!(", FAILED (exit {})", code),
+                (RunStatus::Failed, None) => ", FAILED".to_string(),
+                (status, _) => format!(", {}", status.as_str()),
+            };
+            out.push_str(&format!(
+                "## {} {}{}{}\n",
+                group.keys.len(),
+                noun,
+                label,
+                status
+            ));
+            for key in &group.keys {
+                out.push_str(&format!("  {}\n", key));
+            }
+            if group.stdout.is_empty() {
+                out.push_str("(no output)\n");
+            } else {
+                out.push_str(&group.stdout);
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+        let outliers: usize = self
+            .groups
+            .iter()
+            .filter(|g| g.outlier)
+            .map(|g| g.keys.len())
+            .sum();
+        let plural = |n: usize, word: &str| {
+            if n == 1 {
+                format!("{} {}", n, word)
+            } else {
+                format!("{} {}s", n, word)
+            }
+        };
+        out.push_str(&format!(
+            "{} across {}, {}\n",
+            plural(self.groups.len(), "distinct output"),
+            plural(self.projects.len(), "project"),
+            plural(outliers, "outlier")
+        ));
+        out
+    }
+}
+
 impl BatchListResponse {
     pub fn render_terminal(&self) -> String {
         if self.batches.is_empty() {
@@ -276,14 +492,24 @@ impl BatchListResponse {
         }
         let mut out = String::new();
         for b in &self.batches {
-            let status_str = if b.done { "done" } else { "running" };
+            let status_str = match (b.done, b.failed) {
+                (false, _) => "running".to_string(),
+                (true, 0) => "done".to_string(),
+                (true, n) => format!("done, {} failed", n),
+            };
+            let schedule = b
+                .schedule
+                .as_ref()
+                .map(|s| format!(" (schedule: {})", s))
+                .unwrap_or_default();
             out.push_str(&format!(
-                "{} ({}/{}) [{}] {}\n",
+                "{} ({}/{}) [{}] {}{}\n",
                 b.id,
                 b.completed,
                 b.total,
                 status_str,
-                b.command.join(" ")
+                b.command.join(" "),
+                schedule
             ));
         }
         out
@@ -303,6 +529,7 @@ pub fn create_batch(req: BatchRequest) -> String {
         .into_iter()
         .enumerate()
         .map(|(i, spec)| Run {
+            env: run_env(&spec.key),
             key: spec.key,
             dir: spec.dir,
             status: RunStatus::Pending,
@@ -310,6 +537,7 @@ pub fn create_batch(req: BatchRequest) -> String {
             stdout_path: output_dir.join(format!("{}.stdout", i)),
             stderr_path: output_dir.join(format!("{}.stderr", i)),
             pid: None,
+            pane: None,
             started_at: None,
             finished_at: None,
         })
@@ -318,6 +546,8 @@ pub fn create_batch(req: BatchRequest) -> String {
     let batch = Batch {
         id: id.clone(),
         command: req.command,
+        schedule: req.schedule,
+        tmux: req.tmux,
         created_at: SystemTime::now(),
         runs,
     };
@@ -325,6 +555,14 @@ pub fn create_batch(req: BatchRequest) -> String {
     id
 }
 
+/// Environment for a run, if its key names a known project.
+fn run_env(key: &str) -> Vec<(String, String)> {
+    let project = crate::projects::lock().by_key(&ProjectKey::parse(key));
+    project
+        .map(|p| crate::terminal::batch_env_vars(&p))
+        .unwrap_or_default()
+}
+
 /// Spawn all runs in a batch. Each run gets its own thread.
 pub fn spawn_batch(batch_id: &str) {
     let store = lock();
@@ -334,13 +572,15 @@ pub fn spawn_batch(batch_id: &str) {
     };
 
     let command = batch.command.clone();
-    let run_specs: Vec<(usize, PathBuf, PathBuf, PathBuf)> = batch
+    let in_tmux = batch.tmux;
+    let run_specs: Vec<(usize, String, PathBuf, PathBuf, PathBuf)> = batch
         .runs
         .iter()
         .enumerate()
         .map(|(i, r)| {
             (
                 i,
+                r.key.clone(),
                 r.dir.clone(),
                 r.stdout_path.clone(),
                 r.stderr_path.clone(),
@@ -350,15 +590,133 @@ pub fn spawn_batch(batch_id: &str) {
     let id = batch_id.to_string();
     drop(store);
 
-    for (idx, dir, stdout_path, stderr_path) in run_specs {
+    for (idx, key, dir, stdout_path, stderr_path) in run_specs {
         let cmd = command.clone();
         let batch_id = id.clone();
         std::thread::spawn(move || {
-            run_command(&batch_id, idx, &cmd, &dir, &stdout_path, &stderr_path)
+            if in_tmux {
+                run_in_pane(&batch_id, idx, &cmd, &key, &dir, &stdout_path, &stderr_path)
+            } else {
+                run_command(&batch_id, idx, &cmd, &dir, &stdout_path, &stderr_path)
+            }
         });
     }
 }
 
+/// Run a command with `sh -c` in a new pane of the project's tmux window, so
+/// its output can be watched there. Its stdout, stderr and exit status are
+/// written to the run's files. A run still going after the configured
+/// `batch_pane_timeout` is killed and fails.
+fn run_in_pane(
+    batch_id: &str,
+    idx: usize,
+    command: &[String],
+    key: &str,
+    dir: &Path,
+    stdout_path: &Path,
+    stderr_path: &Path,
+) {
+    let env = {
+        let mut store = lock();
+        let Some(batch) = store.get_mut(batch_id) else {
+            return;
+        };
+        let run = &mut batch.runs[idx];
+        if run.status == RunStatus::Cancelled {
+            return;
+        }
+        run.status = RunStatus::Running;
+        run.started_at = Some(SystemTime::now());
+        let env = run.env.clone();
+        drop(store);
+        notify_change();
+        env
+    };
+
+    let status_path = stdout_path.with_extension("status");
+    let script = pane_script(command, stdout_path, stderr_path, &status_path);
+    let dir = dir.to_string_lossy();
+    let timeout = crate::config::reload_batch_pane_timeout();
+    let started = std::time::Instant::now();
+    let result = crate::tmux::spawn_pane(key, &dir, &env, &script).and_then(|pane| {
+        if let Some(batch) = lock().get_mut(batch_id) {
+            batch.runs[idx].pane = Some(pane.clone());
+        }
+        loop {
+            // A script that finishes quickly may take its pane with it before
+            // the pane is set to remain on exit.
+            let finished = crate::tmux::pane_exit_status(&pane).map_or(true, |s| s.is_some());
+            if finished {
+                crate::tmux::kill_pane(&pane);
+                return fs::read_to_string(&status_path)
+                    .ok()
+                    .and_then(|s| s.trim().parse().ok())
+                    .ok_or_else(|| "the pane exited without an exit status".to_string());
+            }
+            if started.elapsed() > timeout {
+                crate::tmux::kill_pane(&pane);
+                return Err(format!("timed out after {}s", timeout.as_secs()));
+            }
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+    });
+
+    let mut store = lock();
+    if let Some(batch) = store.get_mut(batch_id) {
+        let run = &mut batch.runs[idx];
+        run.finished_at = Some(SystemTime::now());
+        run.pane = None;
+        match result {
+            Ok(code) => {
+                run.exit_code = Some(code);
+                if run.status == RunStatus::Running {
+                    run.status = if code == 0 {
+                        RunStatus::Succeeded
+                    } else {
+                        RunStatus::Failed
+                    };
+                }
+            }
+            Err(e) => {
+                if run.status == RunStatus::Running {
+                    run.status = RunStatus::Failed;
+                    let _ = fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(stderr_path)
+                        .and_then(|mut f| {
+                            std::io::Write::write_all(
+                                &mut f,
+                                format!("tmux error: {}\n", e).as_bytes(),
+                            )
+                        });
+                }
+            }
+        }
+    }
+    notify_change();
+}
+
+/// The POSIX `sh -c` script run in a run's pane: `command`, in a subshell so
+/// that its `exit` still reaches the status line, with its stdout and stderr
+/// both shown in the pane and copied to the run's files, then its exit status
+/// written to `status_path`.
+fn pane_script(
+    command: &[String],
+    stdout_path: &Path,
+    stderr_path: &Path,
+    status_path: &Path,
+) -> String {
+    let path = |p: &Path| shell_escape(&p.to_string_lossy());
+    format!(
+        "{{ {{ (\n{}\n)\necho $? >{}; }} 2>&1 1>&3 3>&- | tee {} 1>&2; }} 3>&1 | tee {}",
+        shell_command_line(command),
+        path(status_path),
+        path(stderr_path),
+        path(stdout_path)
+    )
+}
+
 fn run_command(
     batch_id: &str,
     idx: usize,
@@ -368,18 +726,22 @@ fn run_command(
     stderr_path: &PathBuf,
 ) {
     // Mark running
-    {
+    let env = {
         let mut store = lock();
-        if let Some(batch) = store.get_mut(batch_id) {
-            let run = &mut batch.runs[idx];
-            if run.status == RunStatus::Cancelled {
-                return;
-            }
-            run.status = RunStatus::Running;
-            run.started_at = Some(SystemTime::now());
+        let Some(batch) = store.get_mut(batch_id) else {
+            return;
+        };
+        let run = &mut batch.runs[idx];
+        if run.status == RunStatus::Cancelled {
+            return;
         }
+        run.status = RunStatus::Running;
+        run.started_at = Some(SystemTime::now());
+        let env = run.env.clone();
+        drop(store);
         notify_change();
-    }
+        env
+    };
 
     let stdout_file = fs::File::create(stdout_path).ok();
     let stderr_file = fs::File::create(stderr_path).ok();
@@ -388,6 +750,7 @@ fn run_command(
     let result = std::process::Command::new("sh")
         .args(["-c", &shell_cmd])
         .current_dir(dir)
+        .envs(env)
         .stdout(
             stdout_file
                 .map(std::process::Stdio::from)
@@ -463,7 +826,7 @@ pub fn shell_command_line(command: &[String]) -> String {
         .join(" ")
 }
 
-fn shell_escape(s: &str) -> String {
+pub(crate) fn shell_escape(s: &str) -> String {
     if s.chars()
         .all(|c| c.is_alphanumeric() || "-_./=:@%+,".contains(c))
     {
@@ -492,6 +855,9 @@ pub fn cancel_batch(batch_id: &str) -> bool {
                         libc::kill(pid as i32, libc::SIGTERM);
                     }
                 }
+                if let Some(ref pane) = run.pane {
+                    crate::tmux::kill_pane(pane);
+                }
                 // Status will be updated to Cancelled when the process exits,
                 // but mark it now so the API reflects it immediately.
                 run.status = RunStatus::Cancelled;
@@ -503,6 +869,20 @@ pub fn cancel_batch(batch_id: &str) -> bool {
     true
 }
 
+fn remove_output_files(batch: &Batch) {
+    let mut output_dir = None;
+    for run in &batch.runs {
+        if output_dir.is_none() {
+            output_dir = run.stdout_path.parent().map(|p| p.to_path_buf());
+        }
+        let _ = fs::remove_file(&run.stdout_path);
+        let _ = fs::remove_file(&run.stderr_path);
+    }
+    if let Some(dir) = output_dir {
+        let _ = fs::remove_dir(&dir);
+    }
+}
+
 /// Remove completed batches older than the given duration.
 #[allow(dead_code)]
 pub fn gc(max_age: std::time::Duration) {
@@ -515,18 +895,7 @@ pub fn gc(max_age: std::time::Duration) {
         if batch.created_at > cutoff {
             return true;
         }
-        // Clean up output files and directory
-        let mut output_dir = None;
-        for run in &batch.runs {
-            if output_dir.is_none() {
-                output_dir = run.stdout_path.parent().map(|p| p.to_path_buf());
-            }
-            let _ = fs::remove_file(&run.stdout_path);
-            let _ = fs::remove_file(&run.stderr_path);
-        }
-        if let Some(dir) = output_dir {
-            let _ = fs::remove_dir(&dir);
-        }
+        remove_output_files(batch);
         false
     });
 }
@@ -549,6 +918,8 @@ mod tests {
                     dir: "/tmp".into(),
                 },
             ],
+            schedule: None,
+            tmux: false,
         };
         let id = create_batch(req);
         let store = lock();
@@ -575,6 +946,8 @@ mod tests {
                     dir: "/tmp".into(),
                 },
             ],
+            schedule: None,
+            tmux: false,
         };
         let id = create_batch(req);
         {
@@ -600,6 +973,8 @@ mod tests {
                 key: "gc-test".into(),
                 dir: "/tmp".into(),
             }],
+            schedule: None,
+            tmux: false,
         };
         let id = create_batch(req);
         {
@@ -628,6 +1003,8 @@ mod tests {
                     dir: "/tmp".into(),
                 },
             ],
+            schedule: None,
+            tmux: false,
         };
         let id = create_batch(req);
         {
@@ -718,4 +1095,131 @@ mod tests {
         assert!(out.contains("## proj FAILED\n"), "FAILED without exit code");
         assert!(out.contains("spawn error"), "spawn error shown");
     }
+
+    fn run_response(key: &str, status: RunStatus, stdout: &str) -> RunResponse {
+        RunResponse {
+            key: key.into(),
+            dir: "/tmp".into(),
+            status,
+            exit_code: Some(if status == RunStatus::Succeeded { 0 } else { 1 }),
+            started_at: Some(0.0),
+            finished_at: Some(1.0),
+            stdout: Some(stdout.into()),
+            stderr: None,
+        }
+    }
+
+    fn batch_response(runs: Vec<RunResponse>) -> BatchResponse {
+        BatchResponse {
+            id: "b1".into(),
+            command: vec!["git status --short".into()],
+            created_at: 0.0,
+            total: runs.len(),
+            completed: runs.len(),
+            done: true,
+            runs,
+        }
+    }
+
+    #[test]
+    fn test_report_groups_identical_stdout_and_flags_outliers() {
+        let batch = batch_response(vec![
+            run_response("c", RunStatus::Succeeded, ""),
+            run_response("a", RunStatus::Succeeded, "\n"),
+            run_response("b", RunStatus::Succeeded, " M src/lib.rs\n"),
+            run_response("d", RunStatus::Failed, ""),
+        ]);
+        let report = batch.report();
+        assert_eq!(report.groups.len(), 3);
+        assert_eq!(report.groups[0].keys, vec!["a", "c"]);
+        assert!(!report.groups[0].outlier, "largest group is the baseline");
+        assert_eq!(report.groups[1].keys, vec!["b"]);
+        assert!(report.groups[1].outlier);
+        assert_eq!(report.groups[2].keys, vec!["d"], "failure not in baseline");
+        assert!(report.groups[2].outlier);
+        assert_eq!(report.projects["b"].group, 1);
+        assert!(report.projects["b"].outlier);
+        assert_eq!(report.projects["d"].status, RunStatus::Failed);
+
+        let out = report.render_terminal();
+        assert!(out.contains("## 2 projects\n"), "baseline heading: {out}");
+        assert!(out.contains("## 1 project (outlier)\n"), "outlier heading");
+        assert!(
+            out.contains("## 1 project (outlier), FAILED (exit 1)\n  d\n"),
+            "failed group marked: {out}"
+        );
+        assert!(out.contains("3 distinct outputs across 4 projects, 2 outliers"));
+    }
+
+    #[test]
+    fn test_report_separates_running_and_failed_from_success() {
+        let mut running = run_response("r", RunStatus::Running, "");
+        running.exit_code = None;
+        let batch = batch_response(vec![
+            run_response("a", RunStatus::Succeeded, ""),
+            run_response("b", RunStatus::Succeeded, ""),
+            run_response("f", RunStatus::Failed, ""),
+            running,
+        ]);
+        let report = batch.report();
+        let keys: Vec<_> = report.groups.iter().map(|g| g.keys.clone()).collect();
+        assert_eq!(keys, vec![vec!["a", "b"], vec!["f"], vec!["r"]]);
+        let out = report.render_terminal();
+        assert!(out.contains("## 1 project (outlier), running\n"), "{out}");
+        assert!(out.ends_with("3 distinct outputs across 4 projects, 2 outliers\n"));
+    }
+
+    #[test]
+    fn test_report_tie_has_no_outliers() {
+        let batch = batch_response(vec![
+            run_response("a", RunStatus::Succeeded, "x"),
+            run_response("b", RunStatus::Succeeded, "y"),
+        ]);
+        let report = batch.report();
+        assert_eq!(report.groups.len(), 2);
+        assert!(report.groups.iter().all(|g| !g.outlier));
+    }
+
+    #[test]
+    fn test_report_jsonl_one_line_per_project() {
+        let batch = batch_response(vec![
+            run_response("b", RunStatus::Succeeded, "same"),
+            run_response("a", RunStatus::Succeeded, "same"),
+        ]);
+        let jsonl = batch.report().render_jsonl();
+        let lines: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["key"], "a");
+        assert_eq!(lines[1]["key"], "b");
+        assert_eq!(lines[0]["group"], 0);
+        assert_eq!(lines[0]["stdout"], "same");
+    }
+
+    #[test]
+    fn test_pane_script_writes_output_and_status_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (stdout, stderr, status) = (
+            dir.path().join("0 run.stdout"),
+            dir.path().join("0 run.stderr"),
+            dir.path().join("0 run.status"),
+        );
+        let script = pane_script(
+            &["echo out; echo err >&2; exit 3".to_string()],
+            &stdout,
+            &stderr,
+            &status,
+        );
+        let output = std::process::Command::new("sh")
+            .args(["-c", &script])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "out\n");
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "err\n");
+        assert_eq!(fs::read_to_string(&stdout).unwrap(), "out\n");
+        assert_eq!(fs::read_to_string(&stderr).unwrap(), "err\n");
+        assert_eq!(fs::read_to_string(&status).unwrap(), "3\n");
+    }
 }
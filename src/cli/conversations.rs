@@ -0,0 +1,167 @@
+use std::path::Path;
+
+use crate::conversations::export::Format;
+use crate::conversations::index::SearchHit;
+use crate::conversations::redact::Leak;
+
+use super::util::{encode_path_segment, Client};
+
+pub(super) fn search(
+    client: &Client,
+    query: &[String],
+    project: Option<&str>,
+    since: Option<&str>,
+    until: Option<&str>,
+    limit: Option<usize>,
+    output: &str,
+) -> Result<(), String> {
+    let text = query.join(" ");
+    if text.trim().is_empty() {
+        return Err("Specify search terms".into());
+    }
+    let mut params = vec![format!("q={}", encode_path_segment(&text))];
+    let optional = [("project", project), ("since", since), ("until", until)];
+    for (name, value) in optional {
+        if let Some(v) = value {
+            params.push(format!("{}={}", name, encode_path_segment(v)));
+        }
+    }
+    if let Some(n) = limit {
+        params.push(format!("limit={}", n));
+    }
+    let response = client.get(&format!("/conversations/search?{}", params.join("&")))?;
+    if output == "json" {
+        println!("{}", response);
+        return Ok(());
+    }
+    let hits: Vec<SearchHit> = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    if hits.is_empty() {
+        eprintln!(
+            "No matching conversations (run `wormhole conversations sync` to index new ones)"
+        );
+        return Ok(());
+    }
+    let bold = crate::tty::is_tty();
+    for hit in &hits {
+        println!("{}", render_hit(hit, bold));
+    }
+    eprintln!("Resume one with `wormhole open <path>`");
+    Ok(())
+}
+
+pub(super) fn export(
+    client: &Client,
+    session: &str,
+    file: Option<&Path>,
+    format: Option<&str>,
+) -> Result<(), String> {
+    let format = match format {
+        Some(f) => Format::parse(f)?,
+        None => file
+            .and_then(|f| f.extension())
+            .and_then(|e| Format::parse(&e.to_string_lossy()).ok())
+            .unwrap_or(Format::Markdown),
+    };
+    let format = match format {
+        Format::Markdown => "md",
+        Format::Html => "html",
+    };
+    let doc = client.get(&format!(
+        "/conversations/export/{}?format={}",
+        encode_path_segment(session),
+        format
+    ))?;
+    match file {
+        Some(path) => {
+            std::fs::write(path, doc).map_err(|e| format!("write {}: {}", path.display(), e))?;
+            eprintln!("Wrote {}", path.display());
+        }
+        None => print!("{}", doc),
+    }
+    Ok(())
+}
+
+pub(super) fn scan(client: &Client, redact: bool, output: &str) -> Result<(), String> {
+    let response = if redact {
+        client.post("/conversations/scan?redact=true")?
+    } else {
+        client.get("/conversations/scan")?
+    };
+    if output == "json" {
+        println!("{}", response);
+        return Ok(());
+    }
+    let leaks: Vec<Leak> = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    for leak in &leaks {
+        // Line 0 is the file name
+        let location = match leak.line {
+            0 => leak.path.clone(),
+            line => format!("{}:{}", leak.path, line),
+        };
+        println!("{}  {}  {}", location, leak.kind, leak.preview);
+    }
+    let mut files: Vec<&str> = leaks.iter().map(|l| l.path.as_str()).collect();
+    files.dedup();
+    match (leaks.len(), redact) {
+        (0, _) => eprintln!("No secrets found in synced conversations"),
+        (n, true) => eprintln!("Redacted {} secrets in {} files", n, files.len()),
+        (n, false) => eprintln!(
+            "{} possible secrets in {} files (run with --redact to replace them)",
+            n,
+            files.len()
+        ),
+    }
+    Ok(())
+}
+
+fn render_hit(hit: &SearchHit, bold: bool) -> String {
+    format!(
+        "{}  {}  {}\n  {}\n  {}\n",
+        hit.project,
+        hit.date,
+        hit.title,
+        hit.path,
+        highlight(&hit.snippet, &hit.highlights, bold)
+    )
+}
+
+/// `snippet` with its highlighted ranges in bold.
+fn highlight(snippet: &str, ranges: &[[usize; 2]], bold: bool) -> String {
+    if !bold {
+        return snippet.to_string();
+    }
+    let mut out = String::new();
+    let mut pos = 0;
+    for &[start, end] in ranges {
+        let valid = start >= pos
+            && end <= snippet.len()
+            && snippet.is_char_boundary(start)
+            && snippet.is_char_boundary(end);
+        if !valid {
+            continue;
+        }
+        out.push_str(&snippet[pos..start]);
+        out.push_str("\x1b[1m");
+        out.push_str(&snippet[start..end]);
+        out.push_str("\x1b[0m");
+        pos = end;
+    }
+    out.push_str(&snippet[pos..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_wraps_ranges_in_bold() {
+        let snippet = "fix the flaky test in ci";
+        assert_eq!(
+            highlight(snippet, &[[8, 13], [14, 18]], true),
+            "fix the \x1b[1mflaky\x1b[0m \x1b[1mtest\x1b[0m in ci"
+        );
+        assert_eq!(highlight(snippet, &[[8, 13]], false), snippet);
+        assert_eq!(highlight(snippet, &[[20, 99]], true), snippet);
+    }
+}
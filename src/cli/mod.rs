@@ -9,6 +9,7 @@ use std::io;
 use crate::config;
 use crate::tty::TerminalHyperlink;
 
+mod conversations;
 mod doctor;
 mod jira;
 pub mod project;
@@ -68,6 +69,13 @@ fn complete_projects(current: &std::ffi::OsStr) -> Vec<CompletionCandidate> {
     candidates
 }
 
+fn complete_kv_keys(_current: &std::ffi::OsStr) -> Vec<CompletionCandidate> {
+    crate::kv::schema::KEYS
+        .iter()
+        .map(|k| CompletionCandidate::new(k.name).help(Some(k.description.into())))
+        .collect()
+}
+
 fn complete_available_projects(_current: &std::ffi::OsStr) -> Vec<CompletionCandidate> {
     let url = format!("http://127.0.0.1:{}/project/list", config::wormhole_port());
     let response = match ureq::get(&url).call() {
@@ -206,6 +214,9 @@ pub enum ProjectCommand {
         /// Filter by status (e.g. "done", "in-progress")
         #[arg(long)]
         status: Option<String>,
+        /// Only list projects matching an expression, e.g. 'pr.state=OPEN and kv.task_type!=review'
+        #[arg(long = "where", value_name = "EXPR")]
+        selector: Option<String>,
     },
     /// Switch to the previous project
     Previous {
@@ -277,13 +288,25 @@ pub enum ProjectCommand {
         /// Only run on projects with a tmux window
         #[arg(long)]
         active: bool,
+        /// Only run on projects matching an expression, e.g. 'pr.state=OPEN and kv.task_type!=review'
+        #[arg(long = "where", value_name = "EXPR")]
+        selector: Option<String>,
         /// Show status of running/recent batches
         #[arg(long)]
         status: bool,
         /// Cancel a running batch
         #[arg(long)]
         cancel: Option<String>,
-        /// Output format: text (default) or json
+        /// Run in a new pane of each project's tmux window (see batch_pane_timeout)
+        #[arg(long)]
+        tmux: bool,
+        /// Group runs by identical stdout and highlight outliers
+        #[arg(long)]
+        summary: bool,
+        /// Show the grouped report for an existing batch
+        #[arg(long, value_name = "BATCH_ID")]
+        report: Option<String>,
+        /// Output format: text (default), json, or jsonl (one line per project, with --summary/--report)
         #[arg(short, long, default_value = "text")]
         output: String,
         /// Show progress on stderr
@@ -372,6 +395,12 @@ pub enum Command {
         command: ConversationsCommand,
     },
 
+    /// Recurring batches configured as `[[schedules]]` in wormhole.toml
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommand,
+    },
+
     /// Refresh in-memory data from external sources
     Refresh,
 
@@ -399,6 +428,71 @@ pub enum ConversationsCommand {
         /// With --prune, report what would be pruned without deleting
         #[arg(long)]
         dry_run: bool,
+        /// Include tool calls (name, arguments, truncated result) in the synced files
+        #[arg(long)]
+        tools: bool,
+    },
+    /// Search synced conversations, best matches first
+    Search {
+        /// Words that must all appear
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Only this project, or a repo and all its tasks
+        #[arg(short, long, add = ArgValueCompleter::new(complete_projects))]
+        project: Option<String>,
+        /// Only conversations on or after this date (YYYY-MM-DD) or within this duration (e.g. 2w)
+        #[arg(short, long)]
+        since: Option<String>,
+        /// Only conversations on or before this date (YYYY-MM-DD) or older than this duration
+        #[arg(long)]
+        until: Option<String>,
+        /// Maximum number of results (default 20)
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+        /// Output format: text (default) or json
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Export a synced conversation with its task's key, branch, PR and JIRA issue
+    Export {
+        /// Session UUID (or a unique prefix), or path to a synced file
+        session: String,
+        /// Write to this file instead of stdout
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: Option<std::path::PathBuf>,
+        /// md or html (default: from the file extension, else md)
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Report secrets (AWS keys, GitHub tokens, JWTs, `redact_patterns`) in synced conversations
+    Scan {
+        /// Also replace them in the synced files
+        #[arg(long)]
+        redact: bool,
+        /// Output format: text (default) or json
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Convert other agents' sessions to Claude Code again, with real timestamps and branch
+    Regenerate {
+        /// Only these projects (repeatable)
+        #[arg(short, long, add = ArgValueCompleter::new(complete_projects))]
+        project: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ScheduleCommand {
+    /// List schedules and the outcome of their recent batches
+    List {
+        /// Output format: text (default) or json
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Run a schedule now, regardless of its cron expression
+    Run {
+        /// Schedule name
+        name: String,
     },
 }
 
@@ -449,7 +543,14 @@ pub enum KvCommand {
         /// Project name
         project: String,
         /// Key name
+        #[arg(add = ArgValueCompleter::new(complete_kv_keys))]
         key: String,
+        /// JSON pointer into a structured value, e.g. /build/flags/0
+        #[arg(long, conflicts_with = "resolved")]
+        pointer: Option<String>,
+        /// Fall back to the repo and global scopes, and show where the value came from
+        #[arg(long)]
+        resolved: bool,
         /// Output format: text (default) or json
         #[arg(short, long, default_value = "text")]
         output: String,
@@ -459,20 +560,93 @@ pub enum KvCommand {
         /// Project name
         project: String,
         /// Key name
+        #[arg(add = ArgValueCompleter::new(complete_kv_keys))]
         key: String,
         /// Value to set
         value: String,
+        /// Parse the value as JSON (number, bool, array, object) instead of storing a string
+        #[arg(long)]
+        json: bool,
+        /// JSON pointer into a structured value to set, e.g. /build/flags/- to append
+        #[arg(long)]
+        pointer: Option<String>,
     },
     /// Delete a key (use --all to delete it from every project)
     Delete {
         /// Project name (omit when using --all)
         project: Option<String>,
         /// Key name
+        #[arg(add = ArgValueCompleter::new(complete_kv_keys))]
         key: Option<String>,
+        /// JSON pointer into a structured value; deletes only that element
+        #[arg(long)]
+        pointer: Option<String>,
         /// Delete the key from every project (e.g. clear `land-in` pins)
         #[arg(long)]
         all: bool,
     },
+    /// Show the change history of a project's KV (or one key)
+    History {
+        /// Project name, or @global or @<repo> for a scope
+        project: String,
+        /// Key name (optional, all keys if omitted)
+        #[arg(add = ArgValueCompleter::new(complete_kv_keys))]
+        key: Option<String>,
+        /// Number of most recent changes to show
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+        /// Output format: text (default) or json
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Revert the most recent change to a project's KV (or one key)
+    Undo {
+        /// Project name, or @global or @<repo> for a scope
+        project: String,
+        /// Key name (optional, most recent change to any key if omitted)
+        #[arg(add = ArgValueCompleter::new(complete_kv_keys))]
+        key: Option<String>,
+    },
+    /// Find projects and tasks (open or not) by KV, e.g. `kv find task_type=review`
+    Find {
+        /// Conditions, all of which must hold: key=glob, key!=glob, key~regex,
+        /// key!~regex, or a bare key (set and not "false"). Inherited values count.
+        conditions: Vec<String>,
+        /// Only projects that don't set this key themselves (repeatable)
+        #[arg(long, add = ArgValueCompleter::new(complete_kv_keys))]
+        missing: Vec<String>,
+        /// Output format: text (default) or json
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Export every project's KV (and the repo and global scopes) as one bundle
+    Export {
+        /// File to write (stdout if omitted)
+        file: Option<std::path::PathBuf>,
+        /// Bundle format: json or toml (default: from the file extension, else json)
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Import a bundle written by `kv export`, matching repos by GitHub remote
+    Import {
+        /// Bundle file
+        file: std::path::PathBuf,
+        /// Bundle format: json or toml (default: from the file extension, else json)
+        #[arg(long)]
+        format: Option<String>,
+        /// Replace local values that differ from the bundle (default: report them)
+        #[arg(long)]
+        overwrite: bool,
+        /// Report what would change without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List the keys wormhole itself uses, with their types and meaning
+    Keys {
+        /// Output format: text (default) or json
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
     /// List all KV pairs for a project
     List {
         /// Project name (optional, lists all if omitted)
@@ -536,16 +710,20 @@ pub fn run(command: Command) -> Result<(), String> {
                 tasks,
                 with_editor,
                 status,
+                selector,
             } => {
                 let mut query_parts = vec![];
                 if active {
-                    query_parts.push("active=true");
+                    query_parts.push("active=true".to_string());
                 }
                 if tasks {
-                    query_parts.push("tasks=true");
+                    query_parts.push("tasks=true".to_string());
                 }
                 if with_editor {
-                    query_parts.push("with-editor=true");
+                    query_parts.push("with-editor=true".to_string());
+                }
+                if let Some(expr) = selector {
+                    query_parts.push(format!("where={}", util::encode_path_segment(&expr)));
                 }
                 let path = if query_parts.is_empty() {
                     "/project/list".to_string()
@@ -654,13 +832,30 @@ pub fn run(command: Command) -> Result<(), String> {
             ProjectCommand::ForEach {
                 tasks,
                 active,
+                selector,
                 status,
                 cancel,
+                tmux,
+                summary,
+                report,
                 output,
                 verbose,
                 command,
             } => project::for_each(
-                &client, tasks, active, status, cancel, &command, &output, verbose,
+                &client,
+                &project::ForEachOptions {
+                    tasks_only: tasks,
+                    active,
+                    selector,
+                    status_only: status,
+                    cancel,
+                    summary,
+                    tmux,
+                    report,
+                    output,
+                    verbose,
+                },
+                &command,
             ),
             ProjectCommand::Show { name, output } => {
                 let path = match name {
@@ -723,7 +918,7 @@ pub fn run(command: Command) -> Result<(), String> {
                 let abs_path = std::fs::canonicalize(target_path)
                     .map(|p| p.to_string_lossy().to_string())
                     .unwrap_or(path_str);
-                client.post(&format!("/conversations/resume/{}", abs_path))?;
+                client.post(&crate::conversations::resume_path(&abs_path))?;
             } else if target_path.is_file() {
                 let abs_path = std::fs::canonicalize(target_path)
                     .map(|p| p.to_string_lossy().to_string())
@@ -752,13 +947,26 @@ pub fn run(command: Command) -> Result<(), String> {
             KvCommand::Get {
                 project,
                 key,
+                pointer,
+                resolved,
                 output,
             } => {
-                let response = client.kv_get(&project, &key);
+                let (value, scope) = if resolved {
+                    match client.kv_get_resolved(&project, &key)? {
+                        Some(r) => (Some(r.value), Some(r.scope)),
+                        None => (None, None),
+                    }
+                } else {
+                    (
+                        client.kv_get_json(&project, &key, pointer.as_deref())?,
+                        None,
+                    )
+                };
                 let kv = project::KvValue {
                     project: project.clone(),
                     key: key.clone(),
-                    value: response.ok(),
+                    value,
+                    scope,
                 };
                 if output == "json" {
                     println!(
@@ -774,11 +982,18 @@ pub fn run(command: Command) -> Result<(), String> {
                 project,
                 key,
                 value,
+                json,
+                pointer,
             } => {
-                client.kv_set(&project, &key, &value)?;
+                client.kv_set_at(&project, &key, pointer.as_deref(), json, &value)?;
                 Ok(())
             }
-            KvCommand::Delete { project, key, all } => {
+            KvCommand::Delete {
+                project,
+                key,
+                pointer,
+                all,
+            } => {
                 if all {
                     let kv_key = key.or(project).ok_or(
                         "Specify the key to delete, e.g. `wormhole kv delete --all land-in`",
@@ -791,7 +1006,98 @@ pub fn run(command: Command) -> Result<(), String> {
                 } else {
                     let project = project.ok_or("Specify a project, or use --all")?;
                     let key = key.ok_or("Specify a key")?;
-                    client.kv_delete(&project, &key)?;
+                    client.kv_delete_at(&project, &key, pointer.as_deref())?;
+                }
+                Ok(())
+            }
+            KvCommand::History {
+                project,
+                key,
+                limit,
+                output,
+            } => {
+                let response =
+                    client.get(&kv_history_path("kv-history", &project, key.as_deref()))?;
+                let changes: Vec<crate::kv::history::Change> =
+                    serde_json::from_str(&response).map_err(|e| e.to_string())?;
+                let recent = &changes[changes.len().saturating_sub(limit)..];
+                if output == "json" {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(recent).map_err(|e| e.to_string())?
+                    );
+                } else if recent.is_empty() {
+                    println!("No KV changes recorded");
+                } else {
+                    for change in recent {
+                        println!("{}", change.render_terminal());
+                    }
+                }
+                Ok(())
+            }
+            KvCommand::Undo { project, key } => {
+                let response =
+                    client.post(&kv_history_path("kv-undo", &project, key.as_deref()))?;
+                let change: crate::kv::history::Change =
+                    serde_json::from_str(&response).map_err(|e| e.to_string())?;
+                println!("{}", change.render_terminal());
+                Ok(())
+            }
+            KvCommand::Find {
+                conditions,
+                missing,
+                output,
+            } => {
+                let exprs = conditions
+                    .iter()
+                    .map(|c| crate::selector::kv_condition(c))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut query = vec![];
+                if !exprs.is_empty() {
+                    query.push(format!(
+                        "where={}",
+                        util::encode_path_segment(&exprs.join(" and "))
+                    ));
+                }
+                if !missing.is_empty() {
+                    query.push(format!(
+                        "missing={}",
+                        util::encode_path_segment(&missing.join(","))
+                    ));
+                }
+                let response = client.get(&format!("/kv-find?{}", query.join("&")))?;
+                if output == "json" {
+                    println!("{}", response);
+                } else {
+                    let keys: Vec<String> =
+                        serde_json::from_str(&response).map_err(|e| e.to_string())?;
+                    for key in keys {
+                        println!("{}", key);
+                    }
+                }
+                Ok(())
+            }
+            KvCommand::Export { file, format } => {
+                project::kv_export(&client, file.as_deref(), format.as_deref())
+            }
+            KvCommand::Import {
+                file,
+                format,
+                overwrite,
+                dry_run,
+            } => project::kv_import(&client, &file, format.as_deref(), overwrite, dry_run),
+            KvCommand::Keys { output } => {
+                if output == "json" {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(crate::kv::schema::KEYS)
+                            .map_err(|e| e.to_string())?
+                    );
+                } else {
+                    for k in crate::kv::schema::KEYS {
+                        println!("{:<20} {}", k.name, k.description);
+                        println!("{:<20} ({})", "", k.kind.describe());
+                    }
                 }
                 Ok(())
             }
@@ -804,11 +1110,9 @@ pub fn run(command: Command) -> Result<(), String> {
                     println!("{}", response);
                 } else {
                     // Parse JSON and render text
-                    if let Ok(kv) =
-                        serde_json::from_str::<std::collections::HashMap<String, String>>(&response)
-                    {
+                    if let Ok(kv) = serde_json::from_str::<crate::kv::KvMap>(&response) {
                         for (k, v) in &kv {
-                            println!("{}: {}", k, v);
+                            println!("{}: {}", k, crate::kv::value_to_string(v));
                         }
                     } else {
                         println!("{}", response);
@@ -904,10 +1208,11 @@ pub fn run(command: Command) -> Result<(), String> {
                 since,
                 prune,
                 dry_run,
+                tools,
             } => {
                 let mut params = Vec::new();
                 if !project.is_empty() {
-                    params.push(format!("project={}", project.join(",")));
+                    params.push(format!("project={}", encode_project_list(&project)));
                 }
                 if let Some(ref s) = since {
                     params.push(format!("since={}", s));
@@ -918,6 +1223,9 @@ pub fn run(command: Command) -> Result<(), String> {
                 if dry_run {
                     params.push("dry-run=true".to_string());
                 }
+                if tools {
+                    params.push("tools=true".to_string());
+                }
                 let query = if params.is_empty() {
                     String::new()
                 } else {
@@ -940,6 +1248,72 @@ pub fn run(command: Command) -> Result<(), String> {
                 println!("{}", result.output_dir);
                 Ok(())
             }
+            ConversationsCommand::Search {
+                query,
+                project,
+                since,
+                until,
+                limit,
+                output,
+            } => conversations::search(
+                &client,
+                &query,
+                project.as_deref(),
+                since.as_deref(),
+                until.as_deref(),
+                limit,
+                &output,
+            ),
+            ConversationsCommand::Export {
+                session,
+                file,
+                format,
+            } => conversations::export(&client, &session, file.as_deref(), format.as_deref()),
+            ConversationsCommand::Scan { redact, output } => {
+                conversations::scan(&client, redact, &output)
+            }
+            ConversationsCommand::Regenerate { project } => {
+                let query = if project.is_empty() {
+                    String::new()
+                } else {
+                    format!("?project={}", encode_project_list(&project))
+                };
+                let response = client.post(&format!("/conversations/regenerate{}", query))?;
+                let result: crate::conversations::RegenerateResult =
+                    serde_json::from_str(&response).map_err(|e| e.to_string())?;
+                eprintln!("Regenerated {} Claude Code sessions", result.regenerated);
+                if result.continued > 0 {
+                    eprintln!(
+                        "Left {} alone: continued in Claude Code since they were converted",
+                        result.continued
+                    );
+                }
+                Ok(())
+            }
+        },
+
+        Command::Schedule { command } => match command {
+            ScheduleCommand::List { output } => {
+                let response = client.get("/schedule")?;
+                if output == "json" {
+                    println!("{}", response);
+                } else {
+                    let list: crate::schedule::ScheduleListResponse =
+                        serde_json::from_str(&response).map_err(|e| e.to_string())?;
+                    print!("{}", list.render_terminal());
+                }
+                Ok(())
+            }
+            ScheduleCommand::Run { name } => {
+                let response = client.post(&format!(
+                    "/schedule/{}/run",
+                    util::encode_path_segment(&name)
+                ))?;
+                let batch: crate::batch::BatchResponse =
+                    serde_json::from_str(&response).map_err(|e| e.to_string())?;
+                println!("Started batch {} across {} projects", batch.id, batch.total);
+                Ok(())
+            }
         },
 
         Command::Refresh => {
@@ -26,12 +26,27 @@ impl ProjectDebug {
 pub(super) struct KvValue {
     pub(super) project: String,
     pub(super) key: String,
-    pub(super) value: Option<String>,
+    pub(super) value: Option<serde_json::Value>,
+    /// Where the value came from, for `kv get --resolved`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) scope: Option<crate::kv::scope::Scope>,
 }
 
 impl KvValue {
     pub(super) fn render_terminal(&self) -> String {
-        self.value.clone().unwrap_or_default()
+        let value = self
+            .value
+            .as_ref()
+            .map(crate::kv::value_to_string)
+            .unwrap_or_default();
+        match self.scope {
+            Some(scope) if self.value.is_some() => format!(
+                "{} (from {})",
+                value,
+                serde_json::to_value(scope).unwrap().as_str().unwrap_or("")
+            ),
+            _ => value,
+        }
     }
 }
 
@@ -128,10 +143,16 @@ pub(super) fn render_project_info(info: &crate::status::ProjectInfo) -> String {
     ));
 
     if let Some(status) = info.kv.get("status") {
-        lines.push(format!("Status:     {}", status));
+        lines.push(format!(
+            "Status:     {}",
+            crate::kv::value_to_string(status)
+        ));
     }
     if let Some(visibility) = info.kv.get("visibility") {
-        lines.push(format!("Visibility: {}", visibility));
+        lines.push(format!(
+            "Visibility: {}",
+            crate::kv::value_to_string(visibility)
+        ));
     }
 
     let skip = ["status", "visibility"];
@@ -142,7 +163,23 @@ pub(super) fn render_project_info(info: &crate::status::ProjectInfo) -> String {
         .collect();
     if !other_kv.is_empty() {
         for (k, v) in &other_kv {
-            lines.push(format!("KV {}: {}", k, v));
+            lines.push(format!("KV {}: {}", k, crate::kv::value_to_string(v)));
+        }
+    }
+
+    if !info.conversations.is_empty() {
+        lines.push(format!("Conversations ({}):", info.conversations.len()));
+        for c in &info.conversations {
+            lines.push(format!(
+                "  {}  {}  ({} messages{})",
+                c.date,
+                c.title,
+                c.messages,
+                c.source
+                    .as_deref()
+                    .map(|s| format!(", {}", s))
+                    .unwrap_or_default()
+            ));
         }
     }
 
@@ -191,6 +228,15 @@ pub(super) fn render_task_status(status: &crate::status::TaskStatus) -> String {
             .map(|c| format!(" [{}]", c))
             .unwrap_or_default();
         lines.push(format!("PR:        {}{}", pr_linked, comments));
+        if let Some(checks) = pr.checks_display() {
+            lines.push(format!("Checks:    {}", checks));
+        }
+        if let Some(reviews) = pr.reviews_display() {
+            lines.push(format!("Reviews:   {}", reviews));
+        }
+        if let Some(merge) = pr.merge_display() {
+            lines.push(format!("Merge:     {}", merge));
+        }
     } else {
         lines.push("PR:        ✗".to_string());
     }
@@ -308,19 +354,30 @@ extern "C" fn sigint_handler(_: libc::c_int) {
     INTERRUPTED.store(true, Ordering::Relaxed);
 }
 
+pub(super) struct ForEachOptions {
+    pub(super) tasks_only: bool,
+    pub(super) active: bool,
+    pub(super) selector: Option<String>,
+    pub(super) status_only: bool,
+    pub(super) cancel: Option<String>,
+    pub(super) summary: bool,
+    pub(super) tmux: bool,
+    pub(super) report: Option<String>,
+    pub(super) output: String,
+    pub(super) verbose: bool,
+}
+
 pub(super) fn for_each(
     client: &super::util::Client,
-    tasks_only: bool,
-    active: bool,
-    status_only: bool,
-    cancel: Option<String>,
+    opts: &ForEachOptions,
     command: &[String],
-    output: &str,
-    verbose: bool,
 ) -> Result<(), String> {
-    use crate::batch::{BatchListResponse, BatchResponse};
+    use crate::batch::{BatchListResponse, BatchReport, BatchResponse};
+
+    let output = opts.output.as_str();
+    let verbose = opts.verbose;
 
-    if let Some(batch_id) = cancel {
+    if let Some(ref batch_id) = opts.cancel {
         let response = client.post(&format!("/batch/{}/cancel", batch_id))?;
         if output == "json" {
             println!("{}", response);
@@ -332,7 +389,7 @@ pub(super) fn for_each(
         return Ok(());
     }
 
-    if status_only {
+    if opts.status_only {
         let response = client.get("/batch")?;
         if output == "json" {
             println!("{}", response);
@@ -344,6 +401,12 @@ pub(super) fn for_each(
         return Ok(());
     }
 
+    if let Some(ref batch_id) = opts.report {
+        let response = client.get(&format!("/batch/{}/report", batch_id))?;
+        let report: BatchReport = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+        return print_report(&report, output);
+    }
+
     if command.is_empty() {
         return Err(
             "No command specified. Use -- <command...> or --status to list batches.".into(),
@@ -351,12 +414,19 @@ pub(super) fn for_each(
     }
 
     // Fetch project list
-    let path = if active {
-        "/project/list?active=true"
+    let mut query_parts = vec![];
+    if opts.active {
+        query_parts.push("active=true".to_string());
+    }
+    if let Some(ref expr) = opts.selector {
+        query_parts.push(format!("where={}", super::util::encode_path_segment(expr)));
+    }
+    let path = if query_parts.is_empty() {
+        "/project/list".to_string()
     } else {
-        "/project/list"
+        format!("/project/list?{}", query_parts.join("&"))
     };
-    let response = client.get(path)?;
+    let response = client.get(&path)?;
     let json: serde_json::Value = serde_json::from_str(&response).map_err(|e| e.to_string())?;
 
     let projects = json["current"].as_array().ok_or("No projects found")?;
@@ -365,7 +435,7 @@ pub(super) fn for_each(
         .iter()
         .filter_map(|p| {
             let key = p["project_key"].as_str()?;
-            if tasks_only && !key.contains(':') {
+            if opts.tasks_only && !key.contains(':') {
                 return None;
             }
             let dir = p["path"].as_str()?;
@@ -380,6 +450,7 @@ pub(super) fn for_each(
     let batch_req = serde_json::json!({
         "command": command,
         "runs": runs,
+        "tmux": opts.tmux,
     });
 
     let total = runs.len();
@@ -439,7 +510,9 @@ pub(super) fn for_each(
         }
     }
 
-    if output == "json" {
+    if opts.summary || output == "jsonl" {
+        print_report(&batch.report(), output)?;
+    } else if output == "json" {
         println!(
             "{}",
             serde_json::to_string_pretty(&batch).map_err(|e| e.to_string())?
@@ -459,10 +532,137 @@ pub(super) fn for_each(
     Ok(())
 }
 
+fn print_report(report: &crate::batch::BatchReport, output: &str) -> Result<(), String> {
+    match output {
+        "json" => println!(
+            "{}",
+            serde_json::to_string_pretty(report).map_err(|e| e.to_string())?
+        ),
+        "jsonl" => print!("{}", report.render_jsonl()),
+        _ => print!("{}", report.render_terminal()),
+    }
+    Ok(())
+}
+
+/// `toml` if asked for or if `file` ends in `.toml`, otherwise `json`.
+fn bundle_format(file: Option<&std::path::Path>, format: Option<&str>) -> Result<String, String> {
+    let format = format
+        .map(|f| f.to_string())
+        .or_else(|| {
+            file.and_then(|f| f.extension())
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .filter(|e| e == "toml")
+        })
+        .unwrap_or_else(|| "json".to_string());
+    match format.as_str() {
+        "json" | "toml" => Ok(format),
+        f => Err(format!("Unknown format '{}': use json or toml", f)),
+    }
+}
+
+pub(super) fn kv_export(
+    client: &super::util::Client,
+    file: Option<&std::path::Path>,
+    format: Option<&str>,
+) -> Result<(), String> {
+    let response = client.get("/kv-export")?;
+    let text = match bundle_format(file, format)?.as_str() {
+        "toml" => {
+            let mut bundle: crate::kv::bundle::Bundle =
+                serde_json::from_str(&response).map_err(|e| e.to_string())?;
+            let dropped = bundle.strip_nulls();
+            if dropped > 0 {
+                eprintln!("Dropped {} null value(s): TOML has no null", dropped);
+            }
+            toml::to_string_pretty(&bundle).map_err(|e| e.to_string())?
+        }
+        _ => response,
+    };
+    match file {
+        Some(path) => {
+            std::fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))?;
+            eprintln!("Exported KV to {}", path.display());
+        }
+        None => println!("{}", text.trim_end()),
+    }
+    Ok(())
+}
+
+pub(super) fn kv_import(
+    client: &super::util::Client,
+    file: &std::path::Path,
+    format: Option<&str>,
+    overwrite: bool,
+    dry_run: bool,
+) -> Result<(), String> {
+    let text = std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+    let bundle: crate::kv::bundle::Bundle = match bundle_format(Some(file), format)?.as_str() {
+        "toml" => toml::from_str(&text).map_err(|e| e.to_string())?,
+        _ => serde_json::from_str(&text).map_err(|e| e.to_string())?,
+    };
+    let body = serde_json::to_value(&bundle).map_err(|e| e.to_string())?;
+    let response = client.post_json(
+        &format!("/kv-import?overwrite={}&dry-run={}", overwrite, dry_run),
+        &body,
+    )?;
+    let report: crate::kv::bundle::ImportReport =
+        serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    print!("{}", render_import_report(&report));
+    if !report.failed.is_empty() {
+        return Err(format!(
+            "Failed to write KV for {} target(s)",
+            report.failed.len()
+        ));
+    }
+    Ok(())
+}
+
+fn render_import_report(report: &crate::kv::bundle::ImportReport) -> String {
+    let mut out = format!(
+        "{} {} key(s), {} unchanged, {} conflict(s)\n",
+        if report.dry_run {
+            "Would import"
+        } else {
+            "Imported"
+        },
+        report.imported,
+        report.unchanged,
+        report.conflicts.len()
+    );
+    for c in &report.conflicts {
+        out.push_str(&format!(
+            "  conflict {} {}: local {} vs incoming {}\n",
+            c.target, c.key, c.local, c.incoming
+        ));
+    }
+    for i in &report.invalid {
+        out.push_str(&format!("  skipped {} {}: {}\n", i.target, i.key, i.error));
+    }
+    for repo in &report.unmatched {
+        out.push_str(&format!("  no local project for {}\n", repo));
+    }
+    for f in &report.failed {
+        out.push_str(&format!("  failed {}: {}\n", f.target, f.error));
+    }
+    if !report.conflicts.is_empty() {
+        out.push_str("Use --overwrite to replace conflicting local values\n");
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_bundle_format() {
+        let toml = std::path::Path::new("kv.toml");
+        assert_eq!(bundle_format(Some(toml), None).unwrap(), "toml");
+        assert_eq!(bundle_format(Some(toml), Some("json")).unwrap(), "json");
+        assert_eq!(bundle_format(None, None).unwrap(), "json");
+        assert!(bundle_format(None, Some("yaml")).is_err());
+    }
+
     // Tests run in a non-TTY context, so render_project_item returns plain keys.
 
     #[test]
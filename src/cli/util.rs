@@ -26,6 +26,18 @@ impl Client {
             .map_err(|e| format!("Failed to read response: {}", e))
     }
 
+    /// Like `get`, but `None` for a 404.
+    pub(super) fn get_optional(&self, path: &str) -> Result<Option<String>, String> {
+        match ureq::get(&format!("{}{}", self.base_url, path)).call() {
+            Ok(response) => response
+                .into_string()
+                .map(Some)
+                .map_err(|e| format!("Failed to read response: {}", e)),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(map_ureq_error(e)),
+        }
+    }
+
     pub(super) fn post(&self, path: &str) -> Result<String, String> {
         ureq::post(&format!("{}{}", self.base_url, path))
             .call()
@@ -74,16 +86,84 @@ impl Client {
     }
 
     pub(super) fn kv_set(&self, project: &str, key: &str, value: &str) -> Result<String, String> {
-        self.put(&kv_path(project, Some(key)), value)
+        self.kv_set_at(project, key, None, false, value)
     }
 
     pub(super) fn kv_delete(&self, project: &str, key: &str) -> Result<String, String> {
-        self.delete(&kv_path(project, Some(key)))
+        self.kv_delete_at(project, key, None)
     }
 
     pub(super) fn kv_list(&self, project: &str) -> Result<String, String> {
         self.get(&kv_path(project, None))
     }
+
+    /// Get a value as JSON, or the value at JSON pointer `pointer` within it.
+    pub(super) fn kv_get_json(
+        &self,
+        project: &str,
+        key: &str,
+        pointer: Option<&str>,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let path = kv_path(project, Some(key)) + &kv_query(pointer, true);
+        self.get_optional(&path)?
+            .map(|response| serde_json::from_str(&response).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    /// The effective value of `key`, inherited from the repo or global scope
+    /// if the project doesn't set it.
+    pub(super) fn kv_get_resolved(
+        &self,
+        project: &str,
+        key: &str,
+    ) -> Result<Option<crate::kv::scope::Resolved>, String> {
+        let path = kv_path(project, Some(key)) + "?resolved=true";
+        self.get_optional(&path)?
+            .map(|response| serde_json::from_str(&response).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    /// Set a value, or the value at JSON pointer `pointer` within it. With
+    /// `json`, the server parses `value` as JSON rather than storing a string.
+    pub(super) fn kv_set_at(
+        &self,
+        project: &str,
+        key: &str,
+        pointer: Option<&str>,
+        json: bool,
+        value: &str,
+    ) -> Result<String, String> {
+        let path = kv_path(project, Some(key)) + &kv_query(pointer, json);
+        self.put(&path, value)
+    }
+
+    pub(super) fn kv_delete_at(
+        &self,
+        project: &str,
+        key: &str,
+        pointer: Option<&str>,
+    ) -> Result<String, String> {
+        let path = kv_path(project, Some(key)) + &kv_query(pointer, false);
+        self.delete(&path)
+    }
+}
+
+/// Query string for a `/kv` write, attributing the change to the CLI in the
+/// project's KV history.
+fn kv_query(pointer: Option<&str>, json: bool) -> String {
+    let mut parts = vec!["source=cli".to_string()];
+    if json {
+        parts.push("json=true".to_string());
+    }
+    if let Some(p) = pointer {
+        parts.push(format!("pointer={}", encode_path_segment(p)));
+    }
+    format!("?{}", parts.join("&"))
+}
+
+/// `/kv-history` or `/kv-undo` path for a project and optional key.
+pub(super) fn kv_history_path(prefix: &str, project: &str, key: Option<&str>) -> String {
+    kv_path(project, key).replacen("/kv/", &format!("/{}/", prefix), 1)
 }
 
 fn kv_path(project: &str, key: Option<&str>) -> String {
@@ -262,11 +342,17 @@ pub(super) fn to_kebab_case(s: &str) -> String {
     crate::util::to_kebab_case(s)
 }
 
-/// Percent-encode a value for use as a single URL path segment.
-fn encode_path_segment(s: &str) -> String {
+/// Percent-encode a value for use as a single URL path segment or query value.
+pub(super) fn encode_path_segment(s: &str) -> String {
     percent_encoding::utf8_percent_encode(s, percent_encoding::NON_ALPHANUMERIC).to_string()
 }
 
+/// A comma-separated `project=` query value, each key percent-encoded.
+pub(super) fn encode_project_list(projects: &[String]) -> String {
+    let keys: Vec<String> = projects.iter().map(|p| encode_path_segment(p)).collect();
+    keys.join(",")
+}
+
 /// Returns None if we should prompt, or Some(reason) if we should auto-skip.
 /// Only skips issues that have a non-draft PR (work is already submitted).
 pub(super) fn should_skip_issue(has_pr: bool, status: &str) -> Option<&'static str> {
@@ -297,6 +383,16 @@ mod tests {
         assert_eq!(to_kebab_case("Multiple   spaces"), "multiple-spaces");
     }
 
+    #[test]
+    fn test_encode_project_list_survives_query_parsing() {
+        let projects = vec!["cli:fix&run#2".to_string(), "api:a+b%c".to_string()];
+        let query = format!("project={}", encode_project_list(&projects));
+        let (_, value) = url::form_urlencoded::parse(query.as_bytes())
+            .next()
+            .unwrap();
+        assert_eq!(value.split(',').collect::<Vec<_>>(), projects);
+    }
+
     #[test]
     fn test_should_skip_issues_with_pr() {
         assert_eq!(should_skip_issue(true, "In Progress"), Some("has PR"));
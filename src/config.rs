@@ -125,9 +125,42 @@ struct ConfigFile {
     worktree_dir: Option<String>,
     #[serde(default)]
     card_commands: Vec<String>,
+    /// Add a summary of the task's synced conversations to `.task/card.md`
+    #[serde(default)]
+    card_conversations: bool,
     editor: Option<String>,
     #[serde(default)]
     editors: Vec<EditorOverrideEntry>,
+    #[serde(default)]
+    schedules: Vec<ScheduleEntry>,
+    #[serde(default)]
+    redact_patterns: Vec<String>,
+    /// Seconds a `--tmux` batch run may take before it is killed
+    batch_pane_timeout: Option<u64>,
+    github_api_url: Option<String>,
+    github_token: Option<String>,
+}
+
+/// A recurring batch: `command` is run across projects whenever `cron`
+/// (minute hour day-of-month month day-of-week, local time) matches.
+#[derive(Clone, Debug, Deserialize, serde::Serialize)]
+pub struct ScheduleEntry {
+    pub name: String,
+    pub cron: String,
+    pub command: String,
+    /// Only run on tasks (not plain repos)
+    #[serde(default)]
+    pub tasks: bool,
+    /// Only run on projects with a tmux window
+    #[serde(default)]
+    pub active: bool,
+    /// Number of past batches to retain
+    #[serde(default = "default_schedule_keep")]
+    pub keep: usize,
+}
+
+fn default_schedule_keep() -> usize {
+    5
 }
 
 #[derive(Debug, Deserialize)]
@@ -167,6 +200,8 @@ struct ResolvedConfig {
     search_paths: Vec<ResolvedSearchPath>,
     worktree_dir: PathBuf,
     editor_overrides: Vec<EditorOverride>,
+    github_api_url: String,
+    github_token: Option<String>,
 }
 
 struct EditorOverride {
@@ -234,10 +269,24 @@ fn load_config() -> ResolvedConfig {
         )
         .collect();
 
+    let github_api_url = std::env::var("WORMHOLE_GITHUB_API_URL")
+        .ok()
+        .or(file.github_api_url)
+        .map(|s| s.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| DEFAULT_GITHUB_API_URL.to_string());
+
+    let github_token = ["WORMHOLE_GITHUB_TOKEN", "GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or(file.github_token)
+        .filter(|t| !t.is_empty());
+
     ResolvedConfig {
         search_paths,
         worktree_dir,
         editor_overrides,
+        github_api_url,
+        github_token,
     }
 }
 
@@ -286,6 +335,8 @@ pub fn persist_editor(editor: &Editor) {
     }
 }
 
+const DEFAULT_GITHUB_API_URL: &str = "https://api.github.com";
+
 fn default_worktree_dir() -> PathBuf {
     dirs::home_dir()
         .unwrap_or_else(|| PathBuf::from("/tmp"))
@@ -309,11 +360,63 @@ pub fn worktree_dir() -> &'static Path {
     &config().worktree_dir
 }
 
+/// GitHub REST API base URL, e.g. `https://ghe.example.com/api/v3` for
+/// GitHub Enterprise.
+pub fn github_api_url() -> &'static str {
+    &config().github_api_url
+}
+
+/// The GitHub web host the API URL belongs to: `github.com` for
+/// api.github.com, else the API URL's own host (GitHub Enterprise).
+pub fn github_host() -> &'static str {
+    static HOST: OnceLock<String> = OnceLock::new();
+    HOST.get_or_init(|| host_of_api_url(github_api_url()))
+}
+
+fn host_of_api_url(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split('/').next().unwrap_or(rest);
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host).to_ascii_lowercase();
+    match host.as_str() {
+        "api.github.com" => "github.com".to_string(),
+        _ => host,
+    }
+}
+
+/// GitHub token from the environment or the config file, if any.
+pub fn github_token() -> Option<&'static str> {
+    config().github_token.as_deref()
+}
+
 /// Re-read card_commands from the config file (not cached).
 pub fn reload_card_commands() -> Vec<String> {
     load_config_file().card_commands
 }
 
+/// Re-read card_conversations from the config file (not cached).
+pub fn reload_card_conversations() -> bool {
+    load_config_file().card_conversations
+}
+
+/// Re-read redact_patterns from the config file (not cached).
+pub fn reload_redact_patterns() -> Vec<String> {
+    load_config_file().redact_patterns
+}
+
+/// Re-read batch_pane_timeout from the config file (not cached); one hour if
+/// unset.
+pub fn reload_batch_pane_timeout() -> std::time::Duration {
+    let secs = load_config_file().batch_pane_timeout.unwrap_or(60 * 60);
+    std::time::Duration::from_secs(secs)
+}
+
+/// Re-read schedules from the config file (not cached), so edits take effect
+/// without restarting the server.
+pub fn reload_schedules() -> Vec<ScheduleEntry> {
+    load_config_file().schedules
+}
+
 pub fn is_excluded(name: &str, search_dir: &Path) -> bool {
     for sp in &config().search_paths {
         if sp.path == search_dir {
@@ -451,8 +554,8 @@ mod tests {
 
         assert_eq!(projects.get("project-a"), Some(&dir1.join("project-a")));
         assert_eq!(projects.get("project-b"), Some(&dir2.join("project-b")));
-        assert!(projects.get("repos-project-a").is_none());
-        assert!(projects.get("devenv-project-b").is_none());
+        assert!(!projects.contains_key("repos-project-a"));
+        assert!(!projects.contains_key("devenv-project-b"));
     }
 
     #[test]
@@ -465,7 +568,7 @@ mod tests {
         let paths = vec![dir.clone()];
         let projects = available_projects_from_paths(&paths);
 
-        assert!(projects.get(".hidden").is_none());
+        assert!(!projects.contains_key(".hidden"));
         assert_eq!(projects.get("visible"), Some(&dir.join("visible")));
     }
 
@@ -518,7 +621,7 @@ mod tests {
 
         assert_eq!(projects.get("my-repo"), Some(&repo));
         assert!(
-            projects.get("my-branch").is_none(),
+            !projects.contains_key("my-branch"),
             "Worktrees should not appear as projects"
         );
     }
@@ -566,6 +669,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_host_of_api_url() {
+        assert_eq!(host_of_api_url("https://api.github.com"), "github.com");
+        assert_eq!(
+            host_of_api_url("https://GHE.example.com:8443/api/v3/"),
+            "ghe.example.com"
+        );
+    }
+
     #[test]
     fn test_expand_tilde() {
         let expanded = expand_tilde("~/foo/bar");
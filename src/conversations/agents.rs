@@ -0,0 +1,615 @@
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use std::collections::HashMap;
+
+use super::{
+    flush_message, match_project_path, parse_timestamp_ms, push_tool_result, tool_message, Message,
+    Role, TranscriptFile, TranscriptSource,
+};
+
+/*
+    Transcripts of agents other than Cursor and Claude Code. Each is converted
+    to a Claude Code session on sync (like Cursor's), so it can be resumed.
+    - Codex CLI: `$CODEX_HOME/sessions/YYYY/MM/DD/rollout-*.jsonl` (default
+      `~/.codex`), mapped to a project by the session's cwd.
+    - Aider: `.aider.chat.history.md` in the project dir. The file holds every
+      chat ever started there; each `# aider chat started at` section is a
+      separate transcript.
+    - Continue: `~/.continue/sessions/<id>.json`, mapped by its workspace dir.
+*/
+
+const AIDER_HISTORY_FILE: &str = ".aider.chat.history.md";
+const AIDER_SESSION_HEADER: &str = "# aider chat started at ";
+
+fn codex_sessions_dir() -> PathBuf {
+    std::env::var_os("CODEX_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("/"))
+                .join(".codex")
+        })
+        .join("sessions")
+}
+
+fn continue_sessions_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/"))
+        .join(".continue/sessions")
+}
+
+/// Discover Codex CLI sessions. `canonical` is as for `match_project_path`.
+pub(super) fn discover_codex_transcripts(canonical: &[(String, String)]) -> Vec<TranscriptFile> {
+    discover_codex_in(&codex_sessions_dir(), canonical)
+}
+
+fn discover_codex_in(dir: &Path, canonical: &[(String, String)]) -> Vec<TranscriptFile> {
+    let mut result = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                continue;
+            }
+            let Some((id, cwd)) = read_codex_metadata(&path) else {
+                continue;
+            };
+            if let Some(key) = match_project_path(&cwd, canonical) {
+                result.push(TranscriptFile {
+                    project_key: key,
+                    transcript_id: format!("codex:{}", id),
+                    path,
+                    source: TranscriptSource::Codex,
+                });
+            }
+        }
+    }
+    result
+}
+
+/// The session id and cwd of a Codex rollout file. Current versions write a
+/// `session_meta` record first; older ones a bare `{"id": ...}` line, with the
+/// cwd only in the `<environment_context>` message that follows.
+fn read_codex_metadata(path: &Path) -> Option<(String, String)> {
+    use std::io::BufRead;
+    let file = std::fs::File::open(path).ok()?;
+    let mut id = None;
+    for line in std::io::BufReader::new(file).lines().take(20) {
+        let Ok(line) = line else { break };
+        let Ok(record) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let item = record.get("payload").unwrap_or(&record);
+        if id.is_none() {
+            id = item.get("id").and_then(|v| v.as_str()).map(String::from);
+        }
+        let cwd = item
+            .get("cwd")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .or_else(|| {
+                codex_blocks(item)?
+                    .into_iter()
+                    .find_map(|text| tag_value(text, "cwd"))
+            });
+        if let Some(cwd) = cwd {
+            let id = id.or_else(|| path.file_stem().and_then(|s| s.to_str()).map(String::from))?;
+            return Some((id, cwd));
+        }
+    }
+    None
+}
+
+/// The text blocks of a Codex `message` item, if `item` is one.
+fn codex_blocks(item: &Value) -> Option<Vec<&str>> {
+    if item.get("type").and_then(|t| t.as_str()) != Some("message") {
+        return None;
+    }
+    let blocks = item.get("content")?.as_array()?;
+    Some(
+        blocks
+            .iter()
+            .filter(|b| {
+                matches!(
+                    b.get("type").and_then(|t| t.as_str()),
+                    Some("input_text" | "output_text" | "text")
+                )
+            })
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect(),
+    )
+}
+
+/// The text of a Codex `message` item, without the context Codex injects into
+/// the first user turn.
+fn codex_message_text(item: &Value) -> Option<String> {
+    let parts: Vec<&str> = codex_blocks(item)?
+        .into_iter()
+        .filter(|t| {
+            let t = t.trim_start();
+            !t.starts_with("<environment_context>") && !t.starts_with("<user_instructions>")
+        })
+        .collect();
+    Some(parts.join("\n"))
+}
+
+fn tag_value(text: &str, tag: &str) -> Option<String> {
+    let start = text.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = start + text[start..].find(&format!("</{}>", tag))?;
+    Some(text[start..end].trim().to_string())
+}
+
+pub(super) fn parse_codex_jsonl(path: &Path) -> Result<Vec<Message>, String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    Ok(parse_codex_jsonl_str(&content))
+}
+
+fn parse_codex_jsonl_str(content: &str) -> Vec<Message> {
+    let mut messages = Vec::new();
+    // call_id -> index of its tool message
+    let mut tool_calls = HashMap::new();
+    for line in content.lines() {
+        let Ok(record) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let item = record.get("payload").unwrap_or(&record);
+        let timestamp = record
+            .get("timestamp")
+            .and_then(|t| t.as_str())
+            .and_then(parse_timestamp_ms);
+        let str_field = |name: &str| item.get(name).and_then(|v| v.as_str());
+        match str_field("type") {
+            Some("function_call" | "custom_tool_call" | "local_shell_call") => {
+                let name = str_field("name").unwrap_or("shell");
+                let input = match str_field("arguments").or(str_field("input")) {
+                    Some(input) => input.to_string(),
+                    None => item
+                        .pointer("/action/command")
+                        .and_then(|c| c.as_array())
+                        .map(|c| {
+                            c.iter()
+                                .filter_map(|a| a.as_str())
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        })
+                        .unwrap_or_default(),
+                };
+                if let Some(id) = str_field("call_id") {
+                    tool_calls.insert(id.to_string(), messages.len());
+                }
+                messages.push(Message {
+                    timestamp,
+                    ..tool_message(name, &input)
+                });
+                continue;
+            }
+            Some("function_call_output" | "custom_tool_call_output") => {
+                let output = codex_tool_output(item.get("output"));
+                if let Some(&i) = str_field("call_id").and_then(|id| tool_calls.get(id)) {
+                    push_tool_result(&mut messages[i], &output);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let role = match item.get("role").and_then(|r| r.as_str()) {
+            Some("user") => Role::User,
+            Some("assistant") => Role::Assistant,
+            _ => continue,
+        };
+        let text = codex_message_text(item).unwrap_or_default();
+        let text = text.trim();
+        if !text.is_empty() {
+            messages.push(Message {
+                role,
+                text: text.to_string(),
+                timestamp,
+            });
+        }
+    }
+    messages
+}
+
+/// The text of a Codex tool output: a string, or JSON whose `output` is one.
+fn codex_tool_output(output: Option<&Value>) -> String {
+    let Some(output) = output.and_then(|o| o.as_str()) else {
+        return String::new();
+    };
+    serde_json::from_str::<Value>(output)
+        .ok()
+        .and_then(|v| v.get("output")?.as_str().map(String::from))
+        .unwrap_or_else(|| output.to_string())
+}
+
+/// Discover Aider chats in each project's dir, one transcript per session.
+pub(super) fn discover_aider_transcripts(projects: &[(String, PathBuf)]) -> Vec<TranscriptFile> {
+    let mut result = Vec::new();
+    for (key, dir) in projects {
+        let path = dir.join(AIDER_HISTORY_FILE);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        for started in aider_sessions(&content) {
+            result.push(TranscriptFile {
+                project_key: key.clone(),
+                transcript_id: format!("aider:{}#{}", path.display(), started),
+                path: path.clone(),
+                source: TranscriptSource::Aider,
+            });
+        }
+    }
+    result
+}
+
+/// Start times of the sessions in an Aider history file.
+fn aider_sessions(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .lines()
+        .filter_map(|l| l.strip_prefix(AIDER_SESSION_HEADER))
+        .map(str::trim)
+}
+
+/// The session start time (`YYYY-MM-DD HH:MM:SS`) of an Aider transcript id.
+pub(super) fn aider_started(transcript_id: &str) -> Option<&str> {
+    transcript_id.rsplit_once('#').map(|(_, started)| started)
+}
+
+/// When an Aider session started. Aider writes the start in local time.
+pub(super) fn aider_start_time(transcript_id: &str) -> Option<SystemTime> {
+    let (date, time) = aider_started(transcript_id)?.split_once(' ')?;
+    let mut ymd = date.splitn(3, '-').map(|p| p.parse::<i32>().ok());
+    let mut hms = time.splitn(3, ':').map(|p| p.parse::<i32>().ok());
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = ymd.next()?? - 1900;
+    tm.tm_mon = ymd.next()?? - 1;
+    tm.tm_mday = ymd.next()??;
+    tm.tm_hour = hms.next()??;
+    tm.tm_min = hms.next()??;
+    tm.tm_sec = hms.next()??;
+    tm.tm_isdst = -1;
+    let secs = unsafe { libc::mktime(&mut tm) };
+    let secs = u64::try_from(secs).ok()?;
+    Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+}
+
+pub(super) fn parse_aider_history(path: &Path, started: &str) -> Result<Vec<Message>, String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    Ok(parse_aider_history_str(&content, started))
+}
+
+/// Messages of the session started at `started`. User input is written as
+/// `#### ` lines and command output as `> ` lines (dropped); everything else
+/// is the assistant's reply.
+fn parse_aider_history_str(content: &str, started: &str) -> Vec<Message> {
+    let mut messages = Vec::new();
+    let mut current_role: Option<Role> = None;
+    let mut current_text = String::new();
+    let mut in_session = false;
+
+    for line in content.lines() {
+        if let Some(s) = line.strip_prefix(AIDER_SESSION_HEADER) {
+            if in_session {
+                break;
+            }
+            in_session = s.trim() == started;
+            continue;
+        }
+        if !in_session || line == ">" || line.starts_with("> ") {
+            continue;
+        }
+        if let Some(text) = line.strip_prefix("####") {
+            if current_role != Some(Role::User) {
+                flush_message(&mut messages, &mut current_role, &mut current_text);
+                current_role = Some(Role::User);
+            }
+            current_text.push_str(text.strip_prefix(' ').unwrap_or(text));
+        } else {
+            if current_role != Some(Role::Assistant) {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                flush_message(&mut messages, &mut current_role, &mut current_text);
+                current_role = Some(Role::Assistant);
+            }
+            current_text.push_str(line);
+        }
+        current_text.push('\n');
+    }
+    flush_message(&mut messages, &mut current_role, &mut current_text);
+    messages
+}
+
+/// Discover Continue sessions. `canonical` is as for `match_project_path`.
+pub(super) fn discover_continue_transcripts(canonical: &[(String, String)]) -> Vec<TranscriptFile> {
+    discover_continue_in(&continue_sessions_dir(), canonical)
+}
+
+fn discover_continue_in(dir: &Path, canonical: &[(String, String)]) -> Vec<TranscriptFile> {
+    let mut result = Vec::new();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return result,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        // sessions.json lists the sessions; it isn't one
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if id == "sessions" {
+            continue;
+        }
+        let Some(session) = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+        else {
+            continue;
+        };
+        let turns = continue_messages(&session)
+            .iter()
+            .filter(|m| m.role != Role::Tool)
+            .count();
+        if turns < 2 {
+            continue;
+        }
+        let Some(workspace) = session
+            .get("workspaceDirectory")
+            .and_then(|w| w.as_str())
+            .and_then(workspace_path)
+        else {
+            continue;
+        };
+        if let Some(key) = match_project_path(&workspace, canonical) {
+            result.push(TranscriptFile {
+                project_key: key,
+                transcript_id: format!("continue:{}", id),
+                path,
+                source: TranscriptSource::Continue,
+            });
+        }
+    }
+    result
+}
+
+/// A workspace dir as a path; Continue stores either a path or a file:// URI.
+fn workspace_path(workspace: &str) -> Option<String> {
+    if !workspace.starts_with("file:") {
+        return Some(workspace.to_string());
+    }
+    url::Url::parse(workspace)
+        .ok()?
+        .to_file_path()
+        .ok()
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+pub(super) fn parse_continue_session(path: &Path) -> Result<Vec<Message>, String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    let session: Value =
+        serde_json::from_str(&content).map_err(|e| format!("parse {}: {}", path.display(), e))?;
+    Ok(continue_messages(&session))
+}
+
+fn continue_messages(session: &Value) -> Vec<Message> {
+    let Some(history) = session.get("history").and_then(|h| h.as_array()) else {
+        return Vec::new();
+    };
+    let mut messages = Vec::new();
+    // toolCallId -> index of its tool message
+    let mut tool_calls = HashMap::new();
+    for item in history {
+        let Some(message) = item.get("message") else {
+            continue;
+        };
+        let role = match message.get("role").and_then(|r| r.as_str()) {
+            Some("user") => Role::User,
+            Some("assistant") => Role::Assistant,
+            Some("tool") => {
+                let id = message.get("toolCallId").and_then(|i| i.as_str());
+                let content = message.get("content").and_then(|c| c.as_str());
+                if let (Some(&i), Some(content)) = (id.and_then(|id| tool_calls.get(id)), content) {
+                    push_tool_result(&mut messages[i], content);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let text = match message.get("content") {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Array(parts)) => parts
+                .iter()
+                .filter(|p| p.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => String::new(),
+        };
+        let text = text.trim();
+        if !text.is_empty() {
+            messages.push(Message {
+                role,
+                text: text.to_string(),
+                timestamp: None,
+            });
+        }
+        let calls = message.get("toolCalls").and_then(|c| c.as_array());
+        for call in calls.into_iter().flatten() {
+            let function = call.get("function");
+            let field = |name: &str| {
+                function
+                    .and_then(|f| f.get(name))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+            };
+            if let Some(id) = call.get("id").and_then(|i| i.as_str()) {
+                tool_calls.insert(id.to_string(), messages.len());
+            }
+            messages.push(tool_message(field("name"), field("arguments")));
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_codex_jsonl() {
+        let content = [
+            r#"{"type":"session_meta","payload":{"id":"abc","cwd":"/src/app"}}"#,
+            r#"{"type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"<environment_context>\n<cwd>/src/app</cwd>\n</environment_context>"}]}}"#,
+            r#"{"timestamp":"2026-01-02T10:00:00.250Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"Fix the build"}]}}"#,
+            r#"{"type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{\"command\":[\"cargo\",\"build\"]}","call_id":"c1"}}"#,
+            r#"{"type":"response_item","payload":{"type":"function_call_output","call_id":"c1","output":"{\"output\":\"Finished\",\"metadata\":{}}"}}"#,
+            r#"{"type":"event_msg","payload":{"type":"user_message","message":"Fix the build"}}"#,
+            r#"{"type":"response_item","payload":{"type":"message","role":"assistant","content":[{"type":"output_text","text":"Done."}]}}"#,
+        ]
+        .join("\n");
+        let messages = parse_codex_jsonl_str(&content);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].role, Role::User);
+        assert_eq!(messages[0].text, "Fix the build");
+        assert_eq!(messages[0].timestamp, Some(1_767_348_000_250));
+        assert_eq!(messages[1].role, Role::Tool);
+        assert_eq!(messages[1].timestamp, None);
+        assert!(messages[1]
+            .text
+            .starts_with("shell\n\n```\n{\n  \"command\": ["));
+        assert!(messages[1].text.ends_with("```\nFinished\n```"));
+        assert_eq!(messages[2].role, Role::Assistant);
+        assert_eq!(messages[2].text, "Done.");
+    }
+
+    #[test]
+    fn test_discover_codex_maps_cwd_to_project() {
+        let sessions = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        let canon = std::fs::canonicalize(project.path()).unwrap();
+        let day = sessions.path().join("2026/01/02");
+        std::fs::create_dir_all(&day).unwrap();
+        // Older format: cwd only in the environment context
+        let old = [
+            r#"{"id":"s-old","timestamp":"2026-01-02T00:00:00Z"}"#.to_string(),
+            format!(
+                r#"{{"type":"message","role":"user","content":[{{"type":"input_text","text":"<environment_context>\n<cwd>{}</cwd>\n</environment_context>"}}]}}"#,
+                canon.display()
+            ),
+        ]
+        .join("\n");
+        std::fs::write(day.join("rollout-old.jsonl"), old).unwrap();
+        let new = r#"{"type":"session_meta","payload":{"id":"s-new","cwd":"/elsewhere"}}"#;
+        std::fs::write(day.join("rollout-new.jsonl"), new).unwrap();
+
+        let canonical = vec![(canon.to_string_lossy().to_string(), "app".to_string())];
+        let found = discover_codex_in(sessions.path(), &canonical);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].project_key, "app");
+        assert_eq!(found[0].transcript_id, "codex:s-old");
+        assert_eq!(found[0].source, TranscriptSource::Codex);
+    }
+
+    #[test]
+    fn test_parse_aider_history_selects_session() {
+        let content = "\
+# aider chat started at 2026-01-02 09:00:00
+
+> Aider v0.50.0
+> Main model: gpt-4o
+
+#### add a test
+#### for the parser
+
+Here is the test.
+
+> Applied edit to src/lib.rs
+
+#### thanks
+
+You're welcome.
+
+# aider chat started at 2026-01-03 10:00:00
+
+#### second session
+";
+        assert_eq!(
+            aider_sessions(content).collect::<Vec<_>>(),
+            vec!["2026-01-02 09:00:00", "2026-01-03 10:00:00"]
+        );
+        let messages = parse_aider_history_str(content, "2026-01-02 09:00:00");
+        let texts: Vec<(&Role, &str)> = messages
+            .iter()
+            .map(|m| (&m.role, m.text.as_str()))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                (&Role::User, "add a test\nfor the parser"),
+                (&Role::Assistant, "Here is the test."),
+                (&Role::User, "thanks"),
+                (&Role::Assistant, "You're welcome."),
+            ]
+        );
+        let messages = parse_aider_history_str(content, "2026-01-03 10:00:00");
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].text, "second session");
+        assert_eq!(
+            aider_started("aider:/src/app/.aider.chat.history.md#2026-01-03 10:00:00"),
+            Some("2026-01-03 10:00:00")
+        );
+        let first = aider_start_time("aider:/a#2026-01-02 09:00:00").unwrap();
+        let second = aider_start_time("aider:/a#2026-01-03 10:00:00").unwrap();
+        assert_eq!(second.duration_since(first).unwrap().as_secs(), 25 * 3600);
+        assert!(aider_start_time("aider:/a#yesterday").is_none());
+    }
+
+    #[test]
+    fn test_discover_continue_sessions() {
+        let sessions = tempfile::tempdir().unwrap();
+        let project = tempfile::tempdir().unwrap();
+        let canon = std::fs::canonicalize(project.path()).unwrap();
+        let session = serde_json::json!({
+            "sessionId": "c1",
+            "title": "Refactor",
+            "workspaceDirectory": format!("file://{}", canon.display()),
+            "history": [
+                {"message": {"role": "user", "content": [{"type": "text", "text": "Refactor this"}]}},
+                {"message": {"role": "assistant", "content": "", "toolCalls": [
+                    {"id": "t1", "type": "function", "function": {"name": "read_file", "arguments": "{\"path\":\"a.rs\"}"}}
+                ]}},
+                {"message": {"role": "tool", "content": "fn main() {}", "toolCallId": "t1"}},
+                {"message": {"role": "assistant", "content": "Refactored."}},
+            ],
+        });
+        std::fs::write(sessions.path().join("c1.json"), session.to_string()).unwrap();
+        std::fs::write(sessions.path().join("sessions.json"), "[]").unwrap();
+
+        let canonical = vec![(canon.to_string_lossy().to_string(), "app".to_string())];
+        let found = discover_continue_in(sessions.path(), &canonical);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].transcript_id, "continue:c1");
+        let messages = parse_continue_session(&found[0].path).unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].role, Role::Tool);
+        assert!(messages[1].text.starts_with("read_file\n"));
+        assert!(messages[1].text.ends_with("```\nfn main() {}\n```"));
+        assert_eq!(messages[2].text, "Refactored.");
+    }
+}
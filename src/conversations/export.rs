@@ -0,0 +1,336 @@
+use hyper::{Body, Response, StatusCode};
+use std::path::{Path, PathBuf};
+
+use crate::handlers::dashboard::html_escape;
+use crate::project::ProjectKey;
+use crate::projects;
+
+/*
+    A synced conversation as a standalone document to attach to a PR or JIRA
+    ticket: the conversation's markdown under a header with the task's key,
+    branch, PR and JIRA issue, taken from the server's cached project data.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "md" | "markdown" => Ok(Format::Markdown),
+            "html" | "htm" => Ok(Format::Html),
+            f => Err(format!("Unknown format '{}': use md or html", f)),
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Markdown => "text/markdown; charset=utf-8",
+            Format::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+/// What the header says about the conversation's task.
+#[derive(Debug, Default)]
+pub struct TaskContext {
+    pub key: String,
+    pub branch: Option<String>,
+    pub pr_url: Option<String>,
+    pub jira_key: Option<String>,
+    pub jira_summary: Option<String>,
+}
+
+fn task_context(project_key: &str) -> TaskContext {
+    let key = ProjectKey::parse(project_key);
+    let mut ctx = TaskContext {
+        key: project_key.to_string(),
+        branch: key.branch.as_ref().map(|b| b.to_string()),
+        ..Default::default()
+    };
+    let Some(project) = projects::lock().by_key(&key) else {
+        return ctx;
+    };
+    ctx.pr_url = project.cached.pr.as_ref().map(|pr| pr.url.clone());
+    match project.cached.jira {
+        Some(jira) => {
+            ctx.jira_key = Some(jira.key);
+            ctx.jira_summary = Some(jira.summary);
+        }
+        None => ctx.jira_key = project.kv_str("jira_key").map(String::from),
+    }
+    ctx
+}
+
+/// The synced file of a session, by session UUID (or a unique prefix of one)
+/// or by the path of a file under `output_dir`. When a session has several
+/// files the newest wins.
+pub fn find_session(output_dir: &Path, session: &str) -> Result<PathBuf, String> {
+    let path = Path::new(session);
+    if path.is_absolute() {
+        let inside = |dir: &Path| {
+            let file = path.canonicalize().ok()?;
+            let dir = dir.canonicalize().ok()?;
+            (file.starts_with(dir) && file.is_file()).then_some(file)
+        };
+        return inside(output_dir)
+            .ok_or_else(|| format!("'{}' is not a synced conversation file", session));
+    }
+    let mut matches: Vec<(String, PathBuf)> = vec![];
+    let mut stack = vec![output_dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if path.extension().is_some_and(|e| e == "md") {
+                if let Some((_, uuid)) = super::parse_conversation_header(&path) {
+                    if uuid.starts_with(session) {
+                        matches.push((uuid, path));
+                    }
+                }
+            }
+        }
+    }
+    let mut uuids: Vec<&str> = matches.iter().map(|(u, _)| u.as_str()).collect();
+    uuids.sort();
+    uuids.dedup();
+    match uuids.len() {
+        0 => Err(format!(
+            "No synced conversation for session '{}' (run `wormhole conversations sync`)",
+            session
+        )),
+        1 => Ok(matches
+            .into_iter()
+            .max_by_key(|(_, p)| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+            .map(|(_, p)| p)
+            .unwrap()),
+        _ => Err(format!(
+            "Session '{}' is ambiguous: {}",
+            session,
+            uuids.join(", ")
+        )),
+    }
+}
+
+/// The export of a synced conversation file's `content`.
+pub fn render(content: &str, ctx: &TaskContext, format: Format) -> String {
+    let (header, body) = content.split_once('\n').unwrap_or((content, ""));
+    let header = super::Header::parse(header).unwrap_or_default();
+    let (date, session) = (header.date.as_str(), header.session.as_str());
+    let title = super::index::title_of(content);
+    let title = if title.is_empty() {
+        format!("Conversation {}", session)
+    } else {
+        title
+    };
+
+    let mut md = format!("# {}\n\n", title);
+    md.push_str(&format!("- **Task:** `{}`\n", ctx.key));
+    if let Some(branch) = &ctx.branch {
+        md.push_str(&format!("- **Branch:** `{}`\n", branch));
+    }
+    if let Some(url) = &ctx.pr_url {
+        md.push_str(&format!("- **PR:** <{}>\n", url));
+    }
+    if let Some(key) = &ctx.jira_key {
+        let link = match crate::handlers::describe::jira_url_for_key(key) {
+            Some(url) => format!("[{}]({})", key, url),
+            None => key.clone(),
+        };
+        match &ctx.jira_summary {
+            Some(summary) => md.push_str(&format!("- **JIRA:** {} — {}\n", link, summary)),
+            None => md.push_str(&format!("- **JIRA:** {}\n", link)),
+        }
+    }
+    md.push_str(&format!("- **Date:** {}\n", date));
+    md.push_str(&format!("- **Session:** `{}`\n\n---\n", session));
+    md.push_str(body);
+
+    match format {
+        Format::Markdown => md,
+        Format::Html => html_document(&title, &md),
+    }
+}
+
+fn html_document(title: &str, md: &str) -> String {
+    use pulldown_cmark::{html, Event, Options, Parser, Tag, TagEnd};
+    // Messages are not trusted markup: show any raw HTML in them as text, and
+    // links and images to anything but web, mail or relative URLs as their
+    // text (the page is served from the wormhole origin)
+    let mut kept = Vec::new();
+    let parser = Parser::new_ext(md, Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES)
+        .filter_map(|event| match event {
+            Event::Html(s) | Event::InlineHtml(s) => Some(Event::Text(s)),
+            Event::Start(Tag::Link { ref dest_url, .. })
+            | Event::Start(Tag::Image { ref dest_url, .. }) => {
+                let safe = is_safe_url(dest_url);
+                kept.push(safe);
+                safe.then_some(event)
+            }
+            Event::End(TagEnd::Link) | Event::End(TagEnd::Image) => {
+                kept.pop().unwrap_or(true).then_some(event)
+            }
+            e => Some(e),
+        });
+    let mut body = String::new();
+    html::push_html(&mut body, parser);
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{}</title>
+<style>
+body {{ font-family: -apple-system, BlinkMacSystemFont, sans-serif; max-width: 860px; margin: 2em auto; padding: 0 1em; line-height: 1.5; }}
+pre {{ background: #f6f8fa; padding: 0.8em; overflow-x: auto; }}
+code {{ font-size: 0.9em; }}
+h2 {{ border-bottom: 1px solid #ddd; padding-bottom: 0.2em; }}
+</style>
+</head>
+<body>
+{}</body>
+</html>
+"#,
+        html_escape(title),
+        body
+    )
+}
+
+/// True for http(s), mailto and relative URLs. Browsers ignore whitespace
+/// and control characters in a scheme, so they are dropped before checking.
+fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect();
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => matches!(
+            scheme.to_ascii_lowercase().as_str(),
+            "http" | "https" | "mailto"
+        ),
+        _ => true,
+    }
+}
+
+pub fn export_response(session: &str, format: Option<&str>) -> Response<Body> {
+    let result = Format::parse(format.unwrap_or("md")).and_then(|format| {
+        let path = find_session(&super::conversations_dir(), session)?;
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("read {}: {}", path.display(), e))?;
+        let (project, _) = super::parse_conversation_header(&path).unwrap_or_default();
+        Ok((format, render(&content, &task_context(&project), format)))
+    });
+    match result {
+        Ok((format, doc)) => Response::builder()
+            .header("Content-Type", format.content_type())
+            .body(Body::from(doc))
+            .unwrap(),
+        Err(e) => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from(e))
+            .unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "# cli:feat | 2026-01-02 | 8afac8bb-1234-5678-9abc-def012345678\n\n## User\n\nFix the <b>build</b>\n\n## Assistant\n\nDone.\n";
+
+    fn context() -> TaskContext {
+        TaskContext {
+            key: "cli:feat".to_string(),
+            branch: Some("feat".to_string()),
+            pr_url: Some("https://github.com/acme/cli/pull/7".to_string()),
+            jira_key: Some("ACT-1".to_string()),
+            jira_summary: None,
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_header() {
+        let md = render(CONTENT, &context(), Format::Markdown);
+        assert!(md.starts_with("# Fix the <b>build</b>\n\n- **Task:** `cli:feat`\n"));
+        assert!(md.contains("- **Branch:** `feat`\n"));
+        assert!(md.contains("- **PR:** <https://github.com/acme/cli/pull/7>\n"));
+        assert!(md.contains("ACT-1"));
+        assert!(md.contains("- **Session:** `8afac8bb-1234-5678-9abc-def012345678`\n"));
+        assert!(md.ends_with("## Assistant\n\nDone.\n"));
+    }
+
+    #[test]
+    fn test_render_html_is_standalone_and_escapes_markup() {
+        let html = render(CONTENT, &context(), Format::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Fix the &lt;b&gt;build&lt;/b&gt;</title>"));
+        assert!(html.contains("&lt;b&gt;build&lt;/b&gt;"));
+        assert!(!html.contains("<b>build</b>"));
+        assert!(html.contains(r#"<a href="https://github.com/acme/cli/pull/7">"#));
+    }
+
+    #[test]
+    fn test_render_html_drops_script_links() {
+        let content = "# cli:feat | 2026-01-02 | aaaaaaaa-0000\n\n## Assistant\n\n\
+            [click](javascript:alert(1)) [tab](<java\tscript:x>) ![img](data:text/html,x) \
+            [docs](https://example.com) [local](src/main.rs) [mail](mailto:a@b.c)\n";
+        let html = render(content, &context(), Format::Html);
+        assert!(!html.contains("script:"), "{}", html);
+        assert!(!html.contains("data:text"));
+        assert!(html.contains("click tab img"));
+        assert!(html.contains(r#"<a href="https://example.com">docs</a>"#));
+        assert!(html.contains(r#"<a href="src/main.rs">local</a>"#));
+        assert!(html.contains(r#"<a href="mailto:a@b.c">mail</a>"#));
+    }
+
+    #[test]
+    fn test_find_session_by_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_dir = dir.path().join("cli--feat");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("a.md"), CONTENT).unwrap();
+        std::fs::write(
+            project_dir.join("b.md"),
+            "# cli:feat | 2026-01-03 | 8afbbbbb-0000-0000-0000-000000000000\n",
+        )
+        .unwrap();
+
+        let found = find_session(dir.path(), "8afac8bb").unwrap();
+        assert_eq!(found, project_dir.join("a.md"));
+        assert!(find_session(dir.path(), "8af")
+            .unwrap_err()
+            .contains("ambiguous"));
+        assert!(find_session(dir.path(), "ffff").is_err());
+
+        let by_path = project_dir.join("a.md");
+        assert_eq!(
+            find_session(dir.path(), by_path.to_str().unwrap()).unwrap(),
+            by_path.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_find_session_rejects_paths_outside_output_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("conversations");
+        std::fs::create_dir_all(&output_dir).unwrap();
+        let secret = dir.path().join("id_rsa");
+        std::fs::write(&secret, "PRIVATE KEY").unwrap();
+
+        for path in [
+            secret.clone(),
+            output_dir.join("..").join("id_rsa"),
+            PathBuf::from("/etc/passwd"),
+        ] {
+            let err = find_session(&output_dir, path.to_str().unwrap()).unwrap_err();
+            assert!(err.contains("not a synced conversation"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,645 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use super::timeline::SessionSummary;
+
+/*
+    - An inverted index over the synced `.md` files, stored in
+      `~/.wormhole/conversations-index.json` (outside the conversations dir, so
+      `rg` over that dir never matches it).
+    - Docs are keyed by path relative to the conversations dir. `materialize`
+      records a `Change` for every file it writes or finds up to date, and
+      pruning for every file it removes; they are applied in one go once the
+      files are written, so the lock isn't held while transcripts are parsed.
+      `update` reindexes only files whose mtime changed.
+    - Search requires every query term and ranks with BM25; snippets are cut
+      from the file itself at query time.
+    - Docs also carry what the dashboard and `/project/show` list about each
+      session, so that they need no disk I/O. An index saved by an older
+      version lacks that and is rebuilt by the next sync.
+*/
+
+/// Bumped when `Doc` gains data that older indexes lack.
+const VERSION: u32 = 1;
+
+lazy_static! {
+    static ref INDEX: Mutex<Option<Index>> = Mutex::new(None);
+}
+
+/// A synced file whose index entry needs bringing up to date.
+pub enum Change {
+    /// Reindex the file if its mtime changed
+    Update(PathBuf),
+    /// Reindex the file even if its mtime is unchanged (it may be copied)
+    Reindex(PathBuf),
+    Remove(PathBuf),
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Index {
+    #[serde(default)]
+    version: u32,
+    next_id: u32,
+    /// Relative path -> doc id
+    ids: HashMap<String, u32>,
+    docs: HashMap<u32, Doc>,
+    /// Term -> doc id -> occurrences
+    postings: HashMap<String, HashMap<u32, u32>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Doc {
+    path: String,
+    project: String,
+    date: String,
+    session: String,
+    title: String,
+    /// User and assistant messages
+    #[serde(default)]
+    messages: usize,
+    /// Agent the session came from
+    #[serde(default)]
+    source: Option<String>,
+    /// Seconds since the epoch; the file is reindexed when this changes
+    mtime: u64,
+    /// Number of terms, for length normalization
+    len: u32,
+    /// Distinct terms, for removal
+    terms: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchQuery {
+    pub text: String,
+    /// Project key, or repo name to include its tasks
+    pub project: Option<String>,
+    /// Earliest date, YYYY-MM-DD
+    pub since: Option<String>,
+    /// Latest date, YYYY-MM-DD
+    pub until: Option<String>,
+    pub limit: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub path: String,
+    pub project: String,
+    pub date: String,
+    pub session: String,
+    pub title: String,
+    pub score: f64,
+    pub snippet: String,
+    /// Byte ranges of matched terms within `snippet`
+    pub highlights: Vec<[usize; 2]>,
+    /// POST here to resume the session
+    pub resume_url: String,
+}
+
+fn index_file() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/"))
+        .join(".wormhole/conversations-index.json")
+}
+
+/// When the index was last saved, or None if it never has been.
+pub fn saved_at() -> Option<SystemTime> {
+    std::fs::metadata(index_file())
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+/// The shared index, loaded from disk on first use.
+pub fn lock() -> IndexGuard {
+    let mut guard = INDEX.lock().unwrap();
+    if guard.is_none() {
+        let index = std::fs::read_to_string(index_file())
+            .ok()
+            .and_then(|data| serde_json::from_str::<Index>(&data).ok())
+            .filter(|index| index.version == VERSION)
+            .unwrap_or_else(|| Index {
+                version: VERSION,
+                ..Default::default()
+            });
+        *guard = Some(index);
+    }
+    IndexGuard(guard)
+}
+
+pub struct IndexGuard(MutexGuard<'static, Option<Index>>);
+
+impl std::ops::Deref for IndexGuard {
+    type Target = Index;
+    fn deref(&self) -> &Index {
+        self.0.as_ref().unwrap()
+    }
+}
+
+impl std::ops::DerefMut for IndexGuard {
+    fn deref_mut(&mut self) -> &mut Index {
+        self.0.as_mut().unwrap()
+    }
+}
+
+impl IndexGuard {
+    pub fn save(&self) {
+        let path = index_file();
+        let tmp = path.with_extension("json.tmp");
+        let result = std::fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| std::fs::write(&tmp, serde_json::to_string(&**self).unwrap()))
+            .and_then(|_| std::fs::rename(&tmp, &path));
+        if let Err(e) = result {
+            eprintln!("Failed to save {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Lowercased words of two or more letters, digits or underscores.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !is_word_char(c))
+        .filter(|w| w.chars().count() >= 2 && w.len() <= 64)
+        .map(|w| w.to_lowercase())
+}
+
+/// Byte ranges of the words in `text` that `tokenize` turns into one of
+/// `terms`.
+fn term_ranges(text: &str, terms: &[String]) -> Vec<[usize; 2]> {
+    let mut ranges = vec![];
+    let mut word_start = None;
+    let ends = text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')));
+    for (i, c) in ends {
+        match (is_word_char(c), word_start) {
+            (true, None) => word_start = Some(i),
+            (false, Some(start)) => {
+                if terms.contains(&text[start..i].to_lowercase()) {
+                    ranges.push([start, i]);
+                }
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    ranges
+}
+
+fn mtime_secs(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// The first line of the first user message, shortened.
+pub(super) fn title_of(content: &str) -> String {
+    let line = content
+        .split("\n## User\n")
+        .nth(1)
+        .and_then(|rest| rest.lines().find(|l| !l.trim().is_empty()))
+        .unwrap_or("")
+        .trim();
+    match line.char_indices().nth(80) {
+        Some((i, _)) => format!("{}…", &line[..i]),
+        None => line.to_string(),
+    }
+}
+
+impl Index {
+    /// (Re)index `path` if it changed since it was last indexed.
+    pub fn update(&mut self, output_dir: &Path, path: &Path) {
+        let Ok(rel) = path.strip_prefix(output_dir) else {
+            return;
+        };
+        let rel = rel.to_string_lossy().to_string();
+        let mtime = mtime_secs(path);
+        if let Some(doc) = self.ids.get(&rel).and_then(|id| self.docs.get(id)) {
+            if doc.mtime == mtime {
+                return;
+            }
+        }
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return;
+        };
+        self.insert(rel, mtime, &content);
+    }
+
+    fn insert(&mut self, rel: String, mtime: u64, content: &str) {
+        self.remove_rel(&rel);
+        let Some((project, session)) =
+            super::parse_header_line(content.lines().next().unwrap_or(""))
+        else {
+            return;
+        };
+        let date = content
+            .lines()
+            .next()
+            .and_then(|l| l.split(" | ").nth(1))
+            .unwrap_or("")
+            .to_string();
+        let source = content
+            .lines()
+            .next()
+            .and_then(super::Header::parse)
+            .and_then(|h| h.source);
+        let messages = content
+            .lines()
+            .filter(|l| *l == "## User" || *l == "## Assistant")
+            .count();
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        let mut len = 0;
+        for term in tokenize(content) {
+            *counts.entry(term).or_default() += 1;
+            len += 1;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        for (term, n) in &counts {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(id, *n);
+        }
+        self.ids.insert(rel.clone(), id);
+        self.docs.insert(
+            id,
+            Doc {
+                path: rel,
+                project,
+                date,
+                session,
+                title: title_of(content),
+                messages,
+                source,
+                mtime,
+                len,
+                terms: counts.into_keys().collect(),
+            },
+        );
+    }
+
+    /// The indexed sessions of `project_key`, newest first.
+    pub fn sessions(&self, output_dir: &Path, project_key: &str) -> Vec<SessionSummary> {
+        let mut sessions: Vec<SessionSummary> = self
+            .docs
+            .values()
+            .filter(|doc| doc.project == project_key)
+            .map(|doc| SessionSummary {
+                session: doc.session.clone(),
+                date: doc.date.clone(),
+                title: doc.title.clone(),
+                messages: doc.messages,
+                source: doc.source.clone(),
+                path: output_dir.join(&doc.path).to_string_lossy().to_string(),
+            })
+            .collect();
+        sessions.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| b.path.cmp(&a.path)));
+        sessions
+    }
+
+    pub fn apply(&mut self, output_dir: &Path, changes: &[Change]) {
+        for change in changes {
+            match change {
+                Change::Update(path) => self.update(output_dir, path),
+                Change::Reindex(path) => {
+                    self.remove(output_dir, path);
+                    self.update(output_dir, path);
+                }
+                Change::Remove(path) => self.remove(output_dir, path),
+            }
+        }
+    }
+
+    pub fn remove(&mut self, output_dir: &Path, path: &Path) {
+        if let Ok(rel) = path.strip_prefix(output_dir) {
+            self.remove_rel(&rel.to_string_lossy());
+        }
+    }
+
+    fn remove_rel(&mut self, rel: &str) {
+        let Some(id) = self.ids.remove(rel) else {
+            return;
+        };
+        let Some(doc) = self.docs.remove(&id) else {
+            return;
+        };
+        for term in doc.terms {
+            if let Some(posting) = self.postings.get_mut(&term) {
+                posting.remove(&id);
+                if posting.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Docs containing every term of the query, best first.
+    pub fn search(&self, output_dir: &Path, query: &SearchQuery) -> Vec<SearchHit> {
+        const K1: f64 = 1.2;
+        const B: f64 = 0.75;
+
+        let terms: Vec<String> = tokenize(&query.text).collect();
+        if terms.is_empty() || self.docs.is_empty() {
+            return vec![];
+        }
+        let n = self.docs.len() as f64;
+        let avg_len = self.docs.values().map(|d| d.len as f64).sum::<f64>() / n;
+
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+        for (i, term) in terms.iter().enumerate() {
+            let Some(posting) = self.postings.get(term) else {
+                return vec![];
+            };
+            let idf = (1.0 + (n - posting.len() as f64 + 0.5) / (posting.len() as f64 + 0.5)).ln();
+            let mut next = HashMap::new();
+            for (id, tf) in posting {
+                if i > 0 && !scores.contains_key(id) {
+                    continue;
+                }
+                let doc = &self.docs[id];
+                let tf = *tf as f64;
+                let norm = K1 * (1.0 - B + B * doc.len as f64 / avg_len);
+                let score = idf * tf * (K1 + 1.0) / (tf + norm);
+                next.insert(*id, scores.get(id).copied().unwrap_or(0.0) + score);
+            }
+            scores = next;
+        }
+
+        let mut hits: Vec<(&Doc, f64)> = scores
+            .into_iter()
+            .map(|(id, score)| (&self.docs[&id], score))
+            .filter(|(doc, _)| query.matches(doc))
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| b.0.date.cmp(&a.0.date)));
+        hits.into_iter()
+            .filter_map(|(doc, score)| {
+                let path = output_dir.join(&doc.path);
+                let content = std::fs::read_to_string(&path).ok()?;
+                let (snippet, highlights) = snippet(&content, &terms);
+                let path = path.to_string_lossy().to_string();
+                Some(SearchHit {
+                    resume_url: format!(
+                        "http://127.0.0.1:{}{}",
+                        crate::config::wormhole_port(),
+                        super::resume_path(&path)
+                    ),
+                    path,
+                    project: doc.project.clone(),
+                    date: doc.date.clone(),
+                    session: doc.session.clone(),
+                    title: doc.title.clone(),
+                    score: (score * 100.0).round() / 100.0,
+                    snippet,
+                    highlights,
+                })
+            })
+            .take(query.limit.max(1))
+            .collect()
+    }
+}
+
+impl SearchQuery {
+    fn matches(&self, doc: &Doc) -> bool {
+        let project_ok = self.project.as_deref().is_none_or(|p| {
+            doc.project == p
+                || doc
+                    .project
+                    .strip_prefix(p)
+                    .is_some_and(|rest| rest.starts_with(':'))
+        });
+        project_ok
+            && self.since.as_deref().is_none_or(|s| doc.date.as_str() >= s)
+            && self.until.as_deref().is_none_or(|u| doc.date.as_str() <= u)
+    }
+}
+
+/// About 200 characters of message text around the first query term, on one
+/// line, with the byte ranges of every word in it that matches a term.
+fn snippet(content: &str, terms: &[String]) -> (String, Vec<[usize; 2]>) {
+    const BEFORE: usize = 60;
+    const AFTER: usize = 140;
+
+    let body: String = content
+        .lines()
+        .skip(1)
+        .filter(|l| !l.starts_with("## ") && !l.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let ranges = term_ranges(&body, terms);
+    let first = ranges.first().map_or(0, |r| r[0]);
+    let floor = |mut i: usize| {
+        while !body.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    };
+    let start = floor(first.saturating_sub(BEFORE));
+    let end = floor((first + AFTER).min(body.len()));
+    let window = &body[start..end];
+
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < body.len() { "…" } else { "" };
+    let shift = prefix.len();
+    (
+        format!("{}{}{}", prefix, window, suffix),
+        ranges
+            .into_iter()
+            .filter(|[a, b]| *a >= start && *b <= end)
+            .map(|[a, b]| [a - start + shift, b - start + shift])
+            .collect(),
+    )
+}
+
+/// A `since`/`until` bound as YYYY-MM-DD: either a date already or a duration
+/// like `2w` counted back from now.
+pub fn date_bound(s: &str) -> Result<String, String> {
+    parse_date_bound(s).ok_or_else(|| {
+        format!(
+            "Invalid date '{}': use YYYY-MM-DD or a duration like 3d or 2w",
+            s
+        )
+    })
+}
+
+fn parse_date_bound(s: &str) -> Option<String> {
+    let s = s.trim();
+    let is_date = s.len() == 10
+        && s.chars().enumerate().all(|(i, c)| {
+            if i == 4 || i == 7 {
+                c == '-'
+            } else {
+                c.is_ascii_digit()
+            }
+        });
+    if is_date {
+        return Some(s.to_string());
+    }
+    let days = super::parse_since(s)?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_secs()
+        / 86400;
+    let (y, m, d) = super::epoch_days_to_ymd(days);
+    Some(format!("{:04}-{:02}-{:02}", y, m, d))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(project: &str, date: &str, session: &str, user: &str, assistant: &str) -> String {
+        format!(
+            "# {} | {} | {}\n\n## User\n\n{}\n\n## Assistant\n\n{}\n",
+            project, date, session, user, assistant
+        )
+    }
+
+    fn query(text: &str) -> SearchQuery {
+        SearchQuery {
+            text: text.to_string(),
+            limit: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_ranks_and_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (
+                "cli/a.md",
+                doc(
+                    "cli",
+                    "2026-03-01",
+                    "s1",
+                    "Why does the flaky test fail?",
+                    "The test races on the port.",
+                ),
+            ),
+            (
+                "cli--feat/b.md",
+                doc(
+                    "cli:feat",
+                    "2026-03-05",
+                    "s2",
+                    "Rename the flag",
+                    "Done; the flaky test is unrelated.",
+                ),
+            ),
+            (
+                "api/c.md",
+                doc(
+                    "api",
+                    "2026-02-01",
+                    "s3",
+                    "Flaky flaky flaky test",
+                    "Retry it.",
+                ),
+            ),
+        ];
+        let mut index = Index::default();
+        for (rel, content) in &files {
+            let path = dir.path().join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            index.update(dir.path(), &path);
+        }
+
+        let hits = index.search(dir.path(), &query("FLAKY test"));
+        assert_eq!(hits.len(), 3);
+        assert_eq!(hits[0].session, "s3", "most occurrences ranks first");
+        assert!(index
+            .search(dir.path(), &query("flaky nonexistent"))
+            .is_empty());
+
+        let mut q = query("flaky");
+        q.project = Some("cli".to_string());
+        let sessions: Vec<_> = index
+            .search(dir.path(), &q)
+            .into_iter()
+            .map(|h| h.session)
+            .collect();
+        assert_eq!(sessions.len(), 2, "repo filter includes its tasks");
+        q.since = Some("2026-03-02".to_string());
+        let hits = index.search(dir.path(), &q);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "Rename the flag");
+        assert!(hits[0].resume_url.contains("/conversations/resume/"));
+
+        // Removing a doc drops it and its postings.
+        index.remove(dir.path(), &dir.path().join("api/c.md"));
+        assert_eq!(index.search(dir.path(), &query("retry")).len(), 0);
+        assert!(!index.postings.contains_key("retry"));
+    }
+
+    #[test]
+    fn test_update_reindexes_only_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cli/a.md");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, doc("cli", "2026-03-01", "s1", "alpha", "beta")).unwrap();
+        let mut index = Index::default();
+        index.update(dir.path(), &path);
+        let id = index.ids["cli/a.md"];
+        index.update(dir.path(), &path);
+        assert_eq!(index.ids["cli/a.md"], id, "unchanged file is not reindexed");
+
+        std::fs::write(&path, doc("cli", "2026-03-01", "s1", "gamma", "beta")).unwrap();
+        std::fs::File::open(&path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(5))
+            .unwrap();
+        index.update(dir.path(), &path);
+        assert_eq!(index.search(dir.path(), &query("gamma")).len(), 1);
+        assert!(index.search(dir.path(), &query("alpha")).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_highlights_terms() {
+        let content = doc(
+            "cli",
+            "2026-03-01",
+            "s1",
+            "How do I fix the Port clash?",
+            "Use port 0.",
+        );
+        let (snippet, highlights) = snippet(&content, &["port".to_string()]);
+        assert_eq!(snippet, "How do I fix the Port clash? Use port 0.");
+        let matched: Vec<&str> = highlights.iter().map(|[a, b]| &snippet[*a..*b]).collect();
+        assert_eq!(matched, vec!["Port", "port"]);
+    }
+
+    #[test]
+    fn test_snippet_matches_whole_words_unicode_case() {
+        let content = doc(
+            "cli",
+            "2026-03-01",
+            "s1",
+            "The report mentions ÉTÉ.",
+            "Port été later.",
+        );
+        let terms: Vec<String> = tokenize("port été").collect();
+        let (snippet, highlights) = snippet(&content, &terms);
+        let matched: Vec<&str> = highlights.iter().map(|[a, b]| &snippet[*a..*b]).collect();
+        assert_eq!(matched, vec!["ÉTÉ", "Port", "été"]);
+    }
+
+    #[test]
+    fn test_date_bound() {
+        assert_eq!(date_bound("2026-03-01").as_deref(), Ok("2026-03-01"));
+        assert_eq!(date_bound("2w").map(|d| d.len()), Ok(10));
+        assert!(date_bound("2wk")
+            .unwrap_err()
+            .contains("Invalid date '2wk'"));
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+
+use super::Header;
+use crate::batch::shell_escape;
+
+/*
+    Continuing a synced conversation in an agent CLI, chosen by the project's
+    `agent` KV value (inherited from repo or global scope; default `claude`).
+    - Claude Code resumes the session natively: its own transcripts are already
+      there, and other agents' transcripts are converted to Claude Code JSONL
+      when they are synced.
+    - Agents that can't load a foreign session are given a primer: the synced
+      conversation under a short preamble, written next to the conversations
+      directory and handed to the agent when it starts.
+*/
+
+/// A synced conversation to continue.
+pub struct Session {
+    pub header: Header,
+    pub synced_file: PathBuf,
+}
+
+pub trait ResumeTarget {
+    /// The `agent` KV value selecting this target.
+    fn name(&self) -> &'static str;
+
+    /// Make `session` loadable by the agent, returning the shell command that
+    /// continues it in the project's working tree.
+    fn prepare(&self, session: &Session) -> Result<String, String>;
+}
+
+struct ClaudeCode;
+struct Codex;
+struct Aider;
+
+/// The target for an `agent` KV value; None means Claude Code.
+pub fn target(agent: Option<&str>) -> Result<&'static dyn ResumeTarget, String> {
+    match agent.unwrap_or("claude") {
+        "claude" => Ok(&ClaudeCode),
+        "codex" => Ok(&Codex),
+        "aider" => Ok(&Aider),
+        other => Err(format!(
+            "Unknown agent '{}': use claude, codex or aider",
+            other
+        )),
+    }
+}
+
+impl ResumeTarget for ClaudeCode {
+    fn name(&self) -> &'static str {
+        "claude"
+    }
+
+    fn prepare(&self, session: &Session) -> Result<String, String> {
+        Ok(format!(
+            "claude -r {}",
+            shell_escape(&session.header.session)
+        ))
+    }
+}
+
+impl ResumeTarget for Codex {
+    fn name(&self) -> &'static str {
+        "codex"
+    }
+
+    fn prepare(&self, session: &Session) -> Result<String, String> {
+        let primer = write_primer(&primers_dir(), session)?;
+        let prompt = format!(
+            "Read {} (the transcript of an earlier session on this task) and continue where it left off.",
+            primer.display()
+        );
+        Ok(format!("codex {}", shell_escape(&prompt)))
+    }
+}
+
+impl ResumeTarget for Aider {
+    fn name(&self) -> &'static str {
+        "aider"
+    }
+
+    fn prepare(&self, session: &Session) -> Result<String, String> {
+        let primer = write_primer(&primers_dir(), session)?;
+        Ok(format!(
+            "aider --read {}",
+            shell_escape(&primer.to_string_lossy())
+        ))
+    }
+}
+
+fn primers_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("/"))
+        .join(".wormhole/conversation-primers")
+}
+
+/// Write the primer for `session` into `dir`, returning its path.
+fn write_primer(dir: &Path, session: &Session) -> Result<PathBuf, String> {
+    let content = std::fs::read_to_string(&session.synced_file)
+        .map_err(|e| format!("read {}: {}", session.synced_file.display(), e))?;
+    let body = content.split_once('\n').map_or("", |(_, body)| body);
+    let header = &session.header;
+    let source = header
+        .source
+        .as_deref()
+        .map(|s| format!(" in {}", s))
+        .unwrap_or_default();
+    let primer = format!(
+        "# Earlier session on {}\n\n\
+         This is the transcript of a session from {}{}. Read it for context, \
+         then continue the work where it left off.\n{}",
+        header.project, header.date, source, body
+    );
+    std::fs::create_dir_all(dir).map_err(|e| format!("create dir {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.md", header.session));
+    std::fs::write(&path, primer).map_err(|e| format!("write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_by_agent_name() {
+        assert_eq!(target(None).unwrap().name(), "claude");
+        assert_eq!(target(Some("codex")).unwrap().name(), "codex");
+        assert_eq!(target(Some("aider")).unwrap().name(), "aider");
+        assert!(target(Some("emacs")).is_err_and(|e| e.contains("Unknown agent")));
+    }
+
+    #[test]
+    fn test_write_primer_replaces_header_with_preamble() {
+        let dir = tempfile::tempdir().unwrap();
+        let synced_file = dir.path().join("synced.md");
+        let content =
+            "# cli:feat | 2026-01-02 | 8afac8bb-0000 | cursor\n\n## User\n\nFix the build\n";
+        std::fs::write(&synced_file, content).unwrap();
+        let session = Session {
+            header: Header::parse(content.lines().next().unwrap()).unwrap(),
+            synced_file,
+        };
+
+        let primer = write_primer(&dir.path().join("primers"), &session).unwrap();
+        assert_eq!(primer, dir.path().join("primers/8afac8bb-0000.md"));
+        let text = std::fs::read_to_string(primer).unwrap();
+        assert!(text.starts_with("# Earlier session on cli:feat\n"));
+        assert!(text.contains("from 2026-01-02 in cursor."));
+        assert!(text.ends_with("\n\n## User\n\nFix the build\n"));
+        assert!(Header::parse(text.lines().next().unwrap()).is_none());
+    }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path};
+
+/// A synced session of a task, for the dashboard and `/project/show`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session: String,
+    pub date: String,
+    /// Start of the first user message
+    pub title: String,
+    /// User and assistant messages (tool invocations aren't counted)
+    pub messages: usize,
+    /// Agent the session came from; None for files synced before it was recorded
+    pub source: Option<String>,
+    pub path: String,
+}
+
+/// The synced sessions of `project_key`, newest first. Read from the search
+/// index, which sync keeps up to date, rather than from the files.
+pub fn task_sessions(project_key: &str) -> Vec<SessionSummary> {
+    super::index::lock().sessions(&super::conversations_dir(), project_key)
+}
+
+/// Sessions listed, and scanned for file mentions, in the card summary.
+const CARD_RECENT_SESSIONS: usize = 5;
+const CARD_MAX_FILES: usize = 10;
+
+/// A "where was I" summary of a task's synced `sessions` (newest first) for
+/// its card: how many there are, when the last was, the first prompt of
+/// recent ones and the worktree files they mention. None if there are none.
+pub fn card_summary(sessions: &[SessionSummary], working_tree: &Path) -> Option<String> {
+    let last = sessions.first()?;
+    let recent = &sessions[..sessions.len().min(CARD_RECENT_SESSIONS)];
+    let mut md = format!(
+        "**Conversations:** {} session{}, last on {}\n\n",
+        sessions.len(),
+        if sessions.len() == 1 { "" } else { "s" },
+        last.date
+    );
+    for s in recent {
+        let title = if s.title.is_empty() {
+            "(no prompt)"
+        } else {
+            &s.title
+        };
+        md.push_str(&format!("- {} — {}\n", s.date, title));
+    }
+    let files = mentioned_files(recent, working_tree);
+    if !files.is_empty() {
+        let files: Vec<String> = files.iter().map(|f| format!("`{}`", f)).collect();
+        md.push_str(&format!("\n**Files mentioned:** {}\n", files.join(", ")));
+    }
+    Some(md)
+}
+
+/// Paths mentioned in `sessions` that are files in `working_tree`, most
+/// mentioned first. Paths that could lead out of it (absolute, or with `..`)
+/// are ignored.
+fn mentioned_files(sessions: &[SessionSummary], working_tree: &Path) -> Vec<String> {
+    // Any word may name a file: Makefile and Dockerfile have no extension
+    let Ok(path_re) = regex::Regex::new(r"[A-Za-z0-9_./-]+") else {
+        return vec![];
+    };
+    let prefix = format!("{}/", working_tree.display());
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut not_files: HashSet<String> = HashSet::new();
+    for session in sessions {
+        let Ok(content) = std::fs::read_to_string(&session.path) else {
+            continue;
+        };
+        for m in path_re.find_iter(&content) {
+            let candidate = m.as_str().trim_end_matches('.');
+            let candidate = candidate.strip_prefix(&prefix).unwrap_or(candidate);
+            let candidate = candidate.strip_prefix("./").unwrap_or(candidate);
+            if let Some(count) = counts.get_mut(candidate) {
+                *count += 1;
+            } else if candidate.is_empty() || not_files.contains(candidate) {
+                continue;
+            } else if in_tree(candidate) && working_tree.join(candidate).is_file() {
+                counts.insert(candidate.to_string(), 1);
+            } else {
+                not_files.insert(candidate.to_string());
+            }
+        }
+    }
+    let mut files: Vec<(String, usize)> = counts.into_iter().collect();
+    files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    files
+        .into_iter()
+        .take(CARD_MAX_FILES)
+        .map(|(f, _)| f)
+        .collect()
+}
+
+/// True if `path` is relative and stays below the directory it's joined to.
+fn in_tree(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversations::index::Index;
+
+    /// Index the synced files under `out` and return the sessions of `key`.
+    fn indexed_sessions(out: &Path, key: &str) -> Vec<SessionSummary> {
+        let mut index = Index::default();
+        for dir in std::fs::read_dir(out).unwrap().flatten() {
+            for file in std::fs::read_dir(dir.path()).unwrap().flatten() {
+                index.update(out, &file.path());
+            }
+        }
+        index.sessions(out, key)
+    }
+
+    #[test]
+    fn test_task_sessions_newest_first() {
+        let out = tempfile::tempdir().unwrap();
+        let dir = out.path().join("cli--feat");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("2026-01-02-fix-build-aaaaaaaa.md"),
+            "# cli:feat | 2026-01-02 | aaaaaaaa-0000 | codex | tools\n\n## User\n\nFix the build\n\n## Tool\n\nshell\n\n## Assistant\n\nDone.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("2026-01-05-add-tests-bbbbbbbb.md"),
+            "# cli:feat | 2026-01-05 | bbbbbbbb-0000\n\n## User\n\nAdd tests\n",
+        )
+        .unwrap();
+
+        let sessions = indexed_sessions(out.path(), "cli:feat");
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].session, "bbbbbbbb-0000");
+        assert_eq!(sessions[0].source, None);
+        assert_eq!(sessions[1].title, "Fix the build");
+        assert_eq!(sessions[1].messages, 2);
+        assert_eq!(sessions[1].source.as_deref(), Some("codex"));
+        assert!(indexed_sessions(out.path(), "cli:other").is_empty());
+    }
+
+    #[test]
+    fn test_card_summary_lists_recent_prompts_and_files() {
+        let out = tempfile::tempdir().unwrap();
+        let tree = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tree.path().join("src")).unwrap();
+        std::fs::write(tree.path().join("src/main.rs"), "").unwrap();
+        std::fs::write(tree.path().join("Cargo.toml"), "").unwrap();
+        std::fs::write(tree.path().join("Makefile"), "").unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "").unwrap();
+        let outside_name = outside.path().file_name().unwrap().to_string_lossy();
+        let dir = out.path().join("cli--feat");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("2026-01-02-fix-build-aaaaaaaa.md"),
+            format!(
+                "# cli:feat | 2026-01-02 | aaaaaaaa-0000\n\n## User\n\nFix the build in src/main.rs\n\n## Assistant\n\nEdited {}/src/main.rs and Cargo.toml; see docs/missing.md.\n\nRun make (see Makefile), not ../{}/secret.txt or src/../Makefile.\n",
+                tree.path().display(),
+                outside_name
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("2026-01-05-add-tests-bbbbbbbb.md"),
+            "# cli:feat | 2026-01-05 | bbbbbbbb-0000\n\n## User\n\nAdd tests\n",
+        )
+        .unwrap();
+
+        let md = card_summary(&indexed_sessions(out.path(), "cli:feat"), tree.path()).unwrap();
+        assert_eq!(
+            md,
+            "**Conversations:** 2 sessions, last on 2026-01-05\n\n\
+             - 2026-01-05 — Add tests\n\
+             - 2026-01-02 — Fix the build in src/main.rs\n\n\
+             **Files mentioned:** `src/main.rs`, `Cargo.toml`, `Makefile`\n"
+        );
+        assert!(card_summary(&indexed_sessions(out.path(), "cli:other"), tree.path()).is_none());
+    }
+}
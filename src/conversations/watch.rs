@@ -0,0 +1,239 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use super::{Header, TranscriptFile};
+
+/*
+    - Keeps synced conversations and the search index current without a manual
+      `conversations sync`.
+    - Every POLL_INTERVAL, stat the transcript files under the Cursor and Claude
+      Code project directories. Stats are cheap; discovery (which reads files
+      and runs git) only happens when some transcript's mtime has changed, and
+      only the changed transcripts are re-materialized.
+    - The first poll syncs the transcripts modified since the index was last
+      saved, so sessions that changed while the server was down are current.
+      If the index was never saved, nothing has been synced yet and the first
+      poll only records mtimes.
+    - Each transcript keeps the `tools` mode of its existing synced file, so
+      the watcher never undoes `sync --tools`. New sessions are synced without
+      tool calls.
+    - Converting a Cursor session writes a Claude Code transcript, which is in
+      a watched directory, so mtimes are re-read after syncing to absorb the
+      watcher's own writes.
+    - The project directories transcripts are matched against take a git
+      call per project, so they are kept until the projects change.
+    - An active session changes on nearly every poll. The in-memory index is
+      updated each time, but it is saved at most every INDEX_SAVE_INTERVAL: an
+      index saved before a later change just reindexes that file (its mtime
+      differs) on the next sync.
+*/
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const INDEX_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Watch agent transcripts forever. Spawned on a thread at server startup.
+pub fn run() {
+    let mut watcher = Watcher::default();
+    loop {
+        watcher.poll();
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[derive(Default)]
+struct Watcher {
+    mtimes: HashMap<PathBuf, SystemTime>,
+    primed: bool,
+    /// Whether the in-memory index has changes not yet saved
+    unsaved: bool,
+    last_save: Option<Instant>,
+    /// `project_dirs`, and the project paths they were computed from
+    project_dirs: Option<Vec<(String, PathBuf)>>,
+    project_paths: Vec<(String, PathBuf, PathBuf)>,
+}
+
+impl Watcher {
+    fn poll(&mut self) {
+        let dirs = [
+            super::cursor_projects_dir(),
+            super::claude_code_projects_dir(),
+        ];
+        let mtimes = scan(&dirs);
+        let changed = if std::mem::replace(&mut self.primed, true) {
+            changed_paths(&self.mtimes, &mtimes)
+        } else {
+            super::index::saved_at()
+                .map(|saved| modified_since(&mtimes, saved))
+                .unwrap_or_default()
+        };
+        self.mtimes = mtimes;
+        if !changed.is_empty() && sync_changed(&changed, &self.project_dirs()) > 0 {
+            self.mtimes = scan(&dirs);
+            self.unsaved = true;
+            crate::projects::notify_state_change();
+        }
+        if self.unsaved && save_due(self.last_save, Instant::now()) {
+            super::index::lock().save();
+            self.unsaved = false;
+            self.last_save = Some(Instant::now());
+        }
+    }
+
+    /// The project directories, recomputed only when the projects changed.
+    fn project_dirs(&mut self) -> Vec<(String, PathBuf)> {
+        let paths = super::project_paths();
+        match &self.project_dirs {
+            Some(dirs) if paths == self.project_paths => dirs.clone(),
+            _ => {
+                let dirs = super::dirs_of_projects(paths.clone());
+                self.project_dirs = Some(dirs.clone());
+                self.project_paths = paths;
+                dirs
+            }
+        }
+    }
+}
+
+/// Whether enough time has passed since `last_save` to save the index again.
+fn save_due(last_save: Option<Instant>, now: Instant) -> bool {
+    last_save.is_none_or(|t| now.duration_since(t) >= INDEX_SAVE_INTERVAL)
+}
+
+/// Materialize the Cursor and Claude Code transcripts among `changed` and
+/// update their in-memory index entries. Returns the number of files written.
+fn sync_changed(changed: &HashSet<PathBuf>, projects: &[(String, PathBuf)]) -> usize {
+    let mut transcripts = super::discover_cursor_transcripts(projects);
+    transcripts.extend(super::discover_claude_code_transcripts(projects));
+    let output_dir = super::conversations_dir();
+    let redactor = super::redact::Redactor::from_config();
+    let (with_tools, without_tools): (Vec<TranscriptFile>, Vec<TranscriptFile>) = transcripts
+        .into_iter()
+        .filter(|t| changed.contains(&t.path))
+        .partition(|t| synced_with_tools(t, &output_dir, &redactor));
+
+    let mut changes = Vec::new();
+    let mut synced = 0;
+    for (transcripts, tools) in [(with_tools, true), (without_tools, false)] {
+        if !transcripts.is_empty() {
+            synced += super::materialize(
+                &transcripts,
+                projects,
+                &output_dir,
+                None,
+                None,
+                tools,
+                &mut changes,
+            )
+            .synced;
+        }
+    }
+    super::index::lock().apply(&output_dir, &changes);
+    synced
+}
+
+/// Whether the transcript's current synced file includes tool calls.
+fn synced_with_tools(
+    t: &TranscriptFile,
+    output_dir: &Path,
+    redactor: &super::redact::Redactor,
+) -> bool {
+    Header::read(&super::live_output_path(t, output_dir, redactor)).is_some_and(|h| h.tools)
+}
+
+/// Modification times of the transcript files under `dirs`.
+fn scan(dirs: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+    let mut mtimes = HashMap::new();
+    let mut stack = dirs.to_vec();
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                stack.push(path);
+            } else if path.extension().is_some_and(|e| e == "jsonl" || e == "txt") {
+                if let Ok(mtime) = meta.modified() {
+                    mtimes.insert(path, mtime);
+                }
+            }
+        }
+    }
+    mtimes
+}
+
+/// Files that are new in `current` or whose mtime differs from `previous`.
+fn changed_paths(
+    previous: &HashMap<PathBuf, SystemTime>,
+    current: &HashMap<PathBuf, SystemTime>,
+) -> HashSet<PathBuf> {
+    current
+        .iter()
+        .filter(|(path, mtime)| previous.get(*path) != Some(*mtime))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+/// Files in `mtimes` modified after `since`.
+fn modified_since(mtimes: &HashMap<PathBuf, SystemTime>, since: SystemTime) -> HashSet<PathBuf> {
+    mtimes
+        .iter()
+        .filter(|(_, mtime)| **mtime > since)
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_detects_new_and_modified_transcripts() {
+        let dir = tempfile::tempdir().unwrap();
+        let session_dir = dir.path().join("proj/agent-transcripts/abc");
+        std::fs::create_dir_all(&session_dir).unwrap();
+        let old = session_dir.join("abc.jsonl");
+        std::fs::write(&old, "{}\n").unwrap();
+        std::fs::write(dir.path().join("proj/notes.md"), "ignored").unwrap();
+
+        let dirs = [dir.path().to_path_buf()];
+        let before = scan(&dirs);
+        assert_eq!(before.keys().collect::<Vec<_>>(), vec![&old]);
+        assert!(changed_paths(&before, &scan(&dirs)).is_empty());
+
+        let new = dir.path().join("proj/def.txt");
+        std::fs::write(&new, "user: hi\n").unwrap();
+        let touched = std::fs::File::options().append(true).open(&old).unwrap();
+        touched
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1))
+            .unwrap();
+        let changed = changed_paths(&before, &scan(&dirs));
+        assert_eq!(changed, HashSet::from([old, new]));
+    }
+
+    #[test]
+    fn test_modified_since() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let mtimes = HashMap::from([
+            (PathBuf::from("old.jsonl"), at(10)),
+            (PathBuf::from("new.jsonl"), at(30)),
+        ]);
+        assert_eq!(
+            modified_since(&mtimes, at(20)),
+            HashSet::from([PathBuf::from("new.jsonl")])
+        );
+        assert!(modified_since(&mtimes, at(30)).is_empty());
+    }
+
+    #[test]
+    fn test_index_saves_are_debounced() {
+        let now = Instant::now();
+        assert!(save_due(None, now));
+        assert!(!save_due(Some(now), now + Duration::from_secs(5)));
+        assert!(save_due(Some(now), now + INDEX_SAVE_INTERVAL));
+    }
+}
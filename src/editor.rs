@@ -172,7 +172,7 @@ fn root_workspace_file(project: &Project) -> Option<std::path::PathBuf> {
     let entries = fs::read_dir(&root).ok()?;
     for entry in entries.flatten() {
         let path = entry.path();
-        if path.extension().map_or(false, |e| e == "code-workspace") {
+        if path.extension().is_some_and(|e| e == "code-workspace") {
             return Some(path);
         }
     }
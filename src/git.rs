@@ -51,6 +51,7 @@ pub fn is_worktree(path: &Path) -> bool {
     false
 }
 
+/// The `owner/repo` of the origin remote, if it is on the configured GitHub host.
 pub fn github_repo_from_remote(path: &Path) -> Option<String> {
     let output = Command::new("git")
         .args(["remote", "get-url", "origin"])
@@ -61,21 +62,29 @@ pub fn github_repo_from_remote(path: &Path) -> Option<String> {
         return None;
     }
     let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    parse_github_repo(&url)
+    parse_github_repo(&url, crate::config::github_host())
 }
 
-fn parse_github_repo(url: &str) -> Option<String> {
-    let rest = if let Some(r) = url.strip_prefix("git@github.com:") {
-        r
-    } else if let Some(r) = url.strip_prefix("https://github.com/") {
-        r
-    } else if url.contains("@github.com:") {
-        // Handle org-*@github.com:owner/repo format (GitHub App SSH URLs)
-        url.split("@github.com:").nth(1)?
-    } else {
-        return None;
+/// `owner/repo` from a remote on `host`: `https://host/owner/repo`,
+/// `ssh://git@host:22/owner/repo` or scp-like `git@host:owner/repo` (any user,
+/// e.g. the `org-*@github.com` of GitHub App SSH URLs).
+fn parse_github_repo(url: &str, host: &str) -> Option<String> {
+    let (authority, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?,
+        None => url.split_once(':')?,
     };
-    Some(rest.strip_suffix(".git").unwrap_or(rest).to_string())
+    let authority = authority.rsplit('@').next()?;
+    let remote_host = authority.split(':').next()?;
+    if !remote_host.eq_ignore_ascii_case(host) {
+        return None;
+    }
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, repo) = path.split_once('/')?;
+    if owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None;
+    }
+    Some(path.to_string())
 }
 
 pub struct Worktree {
@@ -314,7 +323,7 @@ fn vacate_branch(path: &Path, branch_name: &str) -> Result<(), String> {
     }
 }
 
-fn is_working_tree_clean(path: &Path) -> bool {
+pub fn is_working_tree_clean(path: &Path) -> bool {
     Command::new("git")
         .args(["status", "--porcelain", "-uno"])
         .current_dir(path)
@@ -363,7 +372,7 @@ pub fn find_orphan_worktree_dirs(repo_path: &Path, worktree_base: &Path) -> Vec<
         .collect();
 
     let mut orphans = vec![];
-    let branch_dirs = match std::fs::read_dir(&base) {
+    let branch_dirs = match std::fs::read_dir(base) {
         Ok(d) => d,
         Err(_) => return vec![],
     };
@@ -403,6 +412,17 @@ pub fn encode_branch_for_path(branch: &str) -> String {
     branch.replace('/', "--")
 }
 
+/// The branch checked out at `path`, or None if HEAD is detached.
+pub fn current_branch(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(path)
+        .output()
+        .ok()?;
+    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && branch != "HEAD").then_some(branch)
+}
+
 pub fn list_branches(repo_path: &Path) -> Vec<String> {
     let output = Command::new("git")
         .args(["for-each-ref", "--format=%(refname:short)", "refs/heads/"])
@@ -487,6 +507,50 @@ pub fn github_file_url(repo_path: &Path, file_path: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_github_repo() {
+        for url in [
+            "git@github.com:acme/cli.git",
+            "org-123@github.com:acme/cli.git",
+            "https://github.com/acme/cli",
+            "https://token@github.com/acme/cli.git",
+            "ssh://git@github.com/acme/cli.git",
+        ] {
+            assert_eq!(
+                parse_github_repo(url, "github.com").as_deref(),
+                Some("acme/cli"),
+                "{}",
+                url
+            );
+        }
+        assert_eq!(
+            parse_github_repo("git@gitlab.com:acme/cli.git", "github.com"),
+            None
+        );
+        assert_eq!(
+            parse_github_repo("https://github.com/acme", "github.com"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_github_enterprise_repo() {
+        let host = "ghe.example.com";
+        for url in [
+            "git@ghe.example.com:acme/cli.git",
+            "ssh://git@ghe.example.com:2222/acme/cli.git",
+            "https://ghe.example.com/acme/cli",
+        ] {
+            assert_eq!(
+                parse_github_repo(url, host).as_deref(),
+                Some("acme/cli"),
+                "{}",
+                url
+            );
+        }
+        assert_eq!(parse_github_repo("git@github.com:acme/cli.git", host), None);
+    }
+
     #[test]
     fn test_parse_worktree_list() {
         let output = r#"worktree /Users/dan/src/temporal
@@ -0,0 +1,453 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::client::Client;
+use super::{count_comments, CheckState, Checks, GithubRef, PrStatus};
+
+/*
+    The GitHub side of `refresh_cache`: every task's PR and review state in
+    one GraphQL query (per BATCH_SIZE tasks), instead of several `gh` calls
+    per task.
+    - A task's PR is the newest one whose head is its branch in its origin
+      repo. PRs from a fork are opened against the parent, so the parent's
+      PRs are searched too, keeping only those whose head repo is the origin.
+    - Comment counts add each author's conversation comments to the inline
+      comments of their reviews.
+    - Checks are the status check rollup of the PR's head commit: GitHub's
+      overall state, plus the names of failed check runs and commit statuses.
+    - Checks and review requests are fetched up to a page of 100; how many
+      more there are is kept so that the display can say the list is partial.
+*/
+
+const BATCH_SIZE: usize = 50;
+
+/// What to look up for one task.
+pub struct TaskQuery {
+    /// `owner/repo` of the task's origin remote
+    pub repo: Option<String>,
+    pub branch: Option<String>,
+    /// A PR the task is reviewing
+    pub review_pr: Option<GithubRef>,
+}
+
+#[derive(Debug, Default)]
+pub struct TaskStatus {
+    pub pr: Option<PrStatus>,
+    /// Whether the viewer has submitted a review on the task's `review_pr`
+    pub review_submitted: Option<bool>,
+}
+
+/// The status of each of `tasks`, in order.
+pub fn task_statuses(client: &Client, tasks: &[TaskQuery]) -> Result<Vec<TaskStatus>, String> {
+    let mut statuses = Vec::with_capacity(tasks.len());
+    for chunk in tasks.chunks(BATCH_SIZE) {
+        match build_query(chunk) {
+            Some(query) => statuses.extend(parse_statuses(chunk, &client.graphql(&query)?)),
+            None => statuses.extend(chunk.iter().map(|_| TaskStatus::default())),
+        }
+    }
+    Ok(statuses)
+}
+
+const PR_FRAGMENT: &str = "fragment pr on PullRequest {
+  number state isDraft url baseRefName mergeable reviewDecision
+  headRepository { nameWithOwner }
+  comments(first: 100) { nodes { author { login } } }
+  reviews(first: 100) { nodes { author { login } comments { totalCount } } }
+  reviewRequests(first: 100) {
+    totalCount
+    nodes { requestedReviewer { ... on User { login } ... on Team { combinedSlug } } }
+  }
+  commits(last: 1) {
+    nodes { commit { statusCheckRollup {
+      state
+      contexts(first: 100) {
+        totalCount
+        nodes { ... on CheckRun { name conclusion } ... on StatusContext { context state } }
+      }
+    } } }
+  }
+}
+";
+
+/// The query for `tasks`, aliasing each task's fields by its index; None if
+/// there is nothing to look up.
+fn build_query(tasks: &[TaskQuery]) -> Option<String> {
+    let mut fields = String::new();
+    let mut uses_fragment = false;
+    for (i, task) in tasks.iter().enumerate() {
+        let repo = task.repo.as_deref().and_then(|r| r.split_once('/'));
+        if let (Some((owner, name)), Some(branch)) = (repo, &task.branch) {
+            let prs = format!(
+                "pullRequests(headRefName: {}, first: 5, orderBy: {{field: CREATED_AT, direction: DESC}}) {{ nodes {{ ...pr }} }}",
+                literal(branch)
+            );
+            fields += &format!(
+                "  pr{}: repository(owner: {}, name: {}) {{ {} parent {{ {} }} }}\n",
+                i,
+                literal(owner),
+                literal(name),
+                prs,
+                prs
+            );
+            uses_fragment = true;
+        }
+        if let Some(r) = &task.review_pr {
+            fields += &format!(
+                "  review{}: repository(owner: {}, name: {}) {{ pullRequest(number: {}) {{ reviews(first: 100) {{ nodes {{ state author {{ login }} }} }} }} }}\n",
+                i,
+                literal(&r.owner),
+                literal(&r.repo),
+                r.number
+            );
+        }
+    }
+    if fields.is_empty() {
+        return None;
+    }
+    let fragment = if uses_fragment { PR_FRAGMENT } else { "" };
+    Some(format!(
+        "query {{\n  viewer {{ login }}\n{}}}\n{}",
+        fields, fragment
+    ))
+}
+
+/// A GraphQL string literal (its escapes are JSON's).
+fn literal(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+fn parse_statuses(tasks: &[TaskQuery], data: &Value) -> Vec<TaskStatus> {
+    let viewer = data["viewer"]["login"].as_str();
+    tasks
+        .iter()
+        .enumerate()
+        .map(|(i, task)| TaskStatus {
+            pr: task
+                .repo
+                .as_deref()
+                .and_then(|repo| find_pr(&data[format!("pr{}", i).as_str()], repo)),
+            review_submitted: task.review_pr.as_ref().map(|_| {
+                let reviews = &data[format!("review{}", i).as_str()]["pullRequest"];
+                viewer.is_some_and(|login| has_review_by(reviews, login))
+            }),
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrNode {
+    number: u64,
+    state: String,
+    is_draft: bool,
+    url: String,
+    head_repository: Option<Repository>,
+    comments: Nodes<CommentNode>,
+    reviews: Nodes<ReviewNode>,
+    base_ref_name: Option<String>,
+    mergeable: Option<String>,
+    review_decision: Option<String>,
+    review_requests: Option<Page<ReviewRequestNode>>,
+    commits: Option<Nodes<CommitNode>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Repository {
+    name_with_owner: String,
+}
+
+#[derive(Deserialize)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+/// The first page of a connection, and the size of the whole of it.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Page<T> {
+    nodes: Vec<T>,
+    total_count: usize,
+}
+
+impl<T> Page<T> {
+    /// How many nodes are beyond this page.
+    fn unlisted(&self) -> usize {
+        self.total_count.saturating_sub(self.nodes.len())
+    }
+}
+
+#[derive(Deserialize)]
+struct CommentNode {
+    author: Option<Author>,
+}
+
+#[derive(Deserialize)]
+struct ReviewNode {
+    author: Option<Author>,
+    comments: TotalCount,
+}
+
+#[derive(Deserialize)]
+struct Author {
+    login: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewRequestNode {
+    requested_reviewer: Option<Reviewer>,
+}
+
+/// A user (login) or team (combinedSlug, e.g. `acme/core`).
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Reviewer {
+    login: Option<String>,
+    combined_slug: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CommitNode {
+    commit: Commit,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Commit {
+    status_check_rollup: Option<Rollup>,
+}
+
+#[derive(Deserialize)]
+struct Rollup {
+    state: String,
+    contexts: Page<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TotalCount {
+    total_count: usize,
+}
+
+/// The newest PR from `repo` among those found in it and in its parent.
+fn find_pr(repository: &Value, repo: &str) -> Option<PrStatus> {
+    let own = &repository["pullRequests"]["nodes"];
+    let parent = &repository["parent"]["pullRequests"]["nodes"];
+    [own, parent]
+        .into_iter()
+        .filter_map(|nodes| serde_json::from_value::<Vec<PrNode>>(nodes.clone()).ok())
+        .flatten()
+        .find(|pr| {
+            pr.head_repository
+                .as_ref()
+                .is_some_and(|h| h.name_with_owner.eq_ignore_ascii_case(repo))
+        })
+        .map(|pr| {
+            let issue_comments = pr.comments.nodes.into_iter().map(|c| (c.author, 1));
+            let review_comments = pr
+                .reviews
+                .nodes
+                .into_iter()
+                .map(|r| (r.author, r.comments.total_count));
+            let comments = count_comments(
+                issue_comments
+                    .chain(review_comments)
+                    .filter_map(|(author, n)| Some((author?.login, n))),
+            );
+            let unlisted_reviewers = pr.review_requests.as_ref().map_or(0, Page::unlisted);
+            let requested_reviewers = pr
+                .review_requests
+                .map(|r| r.nodes)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|r| {
+                    let reviewer = r.requested_reviewer?;
+                    reviewer.login.or(reviewer.combined_slug)
+                })
+                .collect();
+            let checks = pr
+                .commits
+                .and_then(|c| c.nodes.into_iter().next())
+                .and_then(|c| c.commit.status_check_rollup)
+                .map(checks);
+            PrStatus {
+                number: pr.number,
+                state: pr.state,
+                is_draft: pr.is_draft,
+                url: pr.url,
+                comments,
+                base: pr.base_ref_name,
+                checks,
+                review_decision: pr.review_decision,
+                requested_reviewers,
+                unlisted_reviewers,
+                mergeable: pr.mergeable,
+            }
+        })
+}
+
+fn checks(rollup: Rollup) -> Checks {
+    let mut failing: Vec<String> = Vec::new();
+    for context in &rollup.contexts.nodes {
+        if let Some(name) = failed_check_name(context) {
+            if !failing.iter().any(|f| f == name) {
+                failing.push(name.to_string());
+            }
+        }
+    }
+    Checks {
+        state: CheckState::from_rollup(&rollup.state),
+        failing,
+        unlisted: rollup.contexts.unlisted(),
+    }
+}
+
+/// The name of a check run or commit status that failed.
+fn failed_check_name(context: &Value) -> Option<&str> {
+    let failed = |state: &Value| {
+        matches!(
+            state.as_str(),
+            Some(
+                "FAILURE"
+                    | "ERROR"
+                    | "TIMED_OUT"
+                    | "CANCELLED"
+                    | "ACTION_REQUIRED"
+                    | "STARTUP_FAILURE"
+            )
+        )
+    };
+    if failed(&context["conclusion"]) {
+        context["name"].as_str()
+    } else if failed(&context["state"]) {
+        context["context"].as_str()
+    } else {
+        None
+    }
+}
+
+/// Whether `login` has submitted (not just started, and not had dismissed) a
+/// review on the PR.
+fn has_review_by(pull_request: &Value, login: &str) -> bool {
+    pull_request["reviews"]["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|r| {
+            r["author"]["login"].as_str() == Some(login)
+                && !matches!(r["state"].as_str(), Some("PENDING" | "DISMISSED") | None)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::client::mock;
+    use super::*;
+
+    fn query(repo: &str, branch: &str) -> TaskQuery {
+        TaskQuery {
+            repo: Some(repo.into()),
+            branch: Some(branch.into()),
+            review_pr: None,
+        }
+    }
+
+    #[test]
+    fn test_build_query_aliases_tasks_and_escapes_strings() {
+        let review = TaskQuery {
+            repo: None,
+            branch: None,
+            review_pr: super::super::parse_github_ref("acme/api#7"),
+        };
+        let q = build_query(&[query("me/cli", "feat\"x"), review]).unwrap();
+        assert!(q.contains(r#"pr0: repository(owner: "me", name: "cli")"#));
+        assert!(q.contains(r#"headRefName: "feat\"x""#));
+        assert!(q.contains(
+            r#"review1: repository(owner: "acme", name: "api") { pullRequest(number: 7)"#
+        ));
+        assert!(q.ends_with(PR_FRAGMENT));
+
+        let unknown = TaskQuery {
+            repo: None,
+            branch: Some("feat".into()),
+            review_pr: None,
+        };
+        assert!(build_query(&[unknown]).is_none());
+    }
+
+    #[test]
+    fn test_task_statuses_against_mock_server() {
+        let response = serde_json::json!({"data": {
+            "viewer": {"login": "me"},
+            "pr0": {
+                "pullRequests": {"nodes": []},
+                "parent": {"pullRequests": {"nodes": [
+                    {"number": 9, "state": "OPEN", "isDraft": false, "url": "https://github.com/up/cli/pull/9",
+                     "headRepository": {"nameWithOwner": "other/cli"},
+                     "comments": {"nodes": []}, "reviews": {"nodes": []}},
+                    {"number": 8, "state": "OPEN", "isDraft": true, "url": "https://github.com/up/cli/pull/8",
+                     "headRepository": {"nameWithOwner": "Me/cli"},
+                     "baseRefName": "main", "mergeable": "CONFLICTING", "reviewDecision": "CHANGES_REQUESTED",
+                     "reviewRequests": {"totalCount": 2, "nodes": [
+                         {"requestedReviewer": {"login": "carol"}},
+                         {"requestedReviewer": {"combinedSlug": "acme/core"}}
+                     ]},
+                     "commits": {"nodes": [{"commit": {"statusCheckRollup": {"state": "FAILURE", "contexts": {"totalCount": 105, "nodes": [
+                         {"name": "lint", "conclusion": "SUCCESS"},
+                         {"name": "test", "conclusion": "FAILURE"},
+                         {"name": "test", "conclusion": "CANCELLED"},
+                         {"context": "ci/deploy", "state": "ERROR"},
+                         {"context": "ci/docs", "state": "PENDING"}
+                     ]}}}}]},
+                     "comments": {"nodes": [{"author": {"login": "bob"}}, {"author": null}]},
+                     "reviews": {"nodes": [
+                         {"author": {"login": "alice"}, "comments": {"totalCount": 3}},
+                         {"author": {"login": "bob"}, "comments": {"totalCount": 0}}
+                     ]}}
+                ]}}
+            },
+            "pr1": null,
+            "review1": {"pullRequest": {"reviews": {"nodes": [
+                {"state": "PENDING", "author": {"login": "me"}},
+                {"state": "APPROVED", "author": {"login": "alice"}}
+            ]}}},
+            "review2": {"pullRequest": {"reviews": {"nodes": [
+                {"state": "COMMENTED", "author": {"login": "me"}}
+            ]}}}
+        }});
+        let (url, requests) = mock::serve(vec![(200, response.to_string())]);
+        let client = Client::new(&url, Some("secret".into()));
+        let review = |n| super::super::parse_github_ref(&format!("acme/api#{}", n));
+        let tasks = [
+            query("me/cli", "feat"),
+            TaskQuery {
+                review_pr: review(1),
+                ..query("me/gone", "feat")
+            },
+            TaskQuery {
+                repo: None,
+                branch: None,
+                review_pr: review(2),
+            },
+        ];
+
+        let statuses = task_statuses(&client, &tasks).unwrap();
+        assert_eq!(requests.try_iter().count(), 1);
+        let pr = statuses[0].pr.as_ref().unwrap();
+        assert_eq!(pr.display(), "#8 (draft)");
+        assert_eq!(pr.comments_display().unwrap(), "alice:3 bob:1");
+        let checks = pr.checks.as_ref().unwrap();
+        assert_eq!(checks.state, CheckState::Fail);
+        assert_eq!(checks.failing, vec!["test", "ci/deploy"]);
+        assert_eq!(checks.unlisted, 100);
+        assert_eq!(pr.requested_reviewers, vec!["carol", "acme/core"]);
+        assert_eq!(pr.unlisted_reviewers, 0);
+        assert_eq!(pr.merge_display().as_deref(), Some("into main (conflicts)"));
+        assert_eq!(statuses[0].review_submitted, None);
+        assert!(statuses[1].pr.is_none());
+        assert_eq!(statuses[1].review_submitted, Some(false));
+        assert_eq!(statuses[2].review_submitted, Some(true));
+    }
+}
@@ -0,0 +1,263 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/*
+    GitHub's REST and GraphQL APIs over HTTP.
+    - The base URL is the REST root: `github_api_url` in wormhole.toml (or
+      WORMHOLE_GITHUB_API_URL), default https://api.github.com. GraphQL is at
+      `<base>/graphql`, except for a GitHub Enterprise `<host>/api/v3` base,
+      where it is `<host>/api/graphql`.
+    - The token is WORMHOLE_GITHUB_TOKEN, GITHUB_TOKEN, GH_TOKEN or
+      `github_token`, falling back to `gh auth token` so that an existing gh
+      login keeps working. REST calls work without one for public repos;
+      GraphQL always needs one.
+*/
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+pub struct Client {
+    base_url: String,
+    token: Option<String>,
+    agent: ureq::Agent,
+}
+
+/// The client configured by wormhole.toml and the environment.
+pub fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        let token = crate::config::github_token()
+            .map(String::from)
+            .or_else(gh_auth_token);
+        Client::new(crate::config::github_api_url(), token)
+    })
+}
+
+fn gh_auth_token() -> Option<String> {
+    let output = Command::new("gh").args(["auth", "token"]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let token = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!token.is_empty()).then_some(token)
+}
+
+impl Client {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Client {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+        }
+    }
+
+    fn graphql_url(&self) -> String {
+        match self.base_url.strip_suffix("/api/v3") {
+            Some(host) => format!("{}/api/graphql", host),
+            None => format!("{}/graphql", self.base_url),
+        }
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = self
+            .agent
+            .request(method, url)
+            .set("Accept", "application/vnd.github+json")
+            .set("User-Agent", "wormhole");
+        match &self.token {
+            Some(token) => request.set("Authorization", &format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    /// GET `path` (relative to the base URL); None if it doesn't exist.
+    pub fn get<T: DeserializeOwned>(&self, path: &str) -> Result<Option<T>, String> {
+        let url = format!("{}/{}", self.base_url, path);
+        match self.request("GET", &url).call() {
+            Ok(resp) => resp
+                .into_json()
+                .map(Some)
+                .map_err(|e| format!("Failed to parse GitHub response: {}", e)),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(request_error(e)),
+        }
+    }
+
+    /// Run a GraphQL query, returning its `data`. A field that fails (e.g. a
+    /// repository that doesn't exist) is null in `data` rather than an error.
+    pub fn graphql(&self, query: &str) -> Result<Value, String> {
+        if self.token.is_none() {
+            return Err(
+                "No GitHub token: set GITHUB_TOKEN or github_token, or run gh auth login".into(),
+            );
+        }
+        let body: Value = self
+            .request("POST", &self.graphql_url())
+            .send_json(serde_json::json!({ "query": query }))
+            .map_err(request_error)?
+            .into_json()
+            .map_err(|e| format!("Failed to parse GitHub response: {}", e))?;
+        match body.get("data") {
+            Some(data) if !data.is_null() => Ok(data.clone()),
+            _ => Err(format!(
+                "GitHub GraphQL query failed: {}",
+                error_messages(&body)
+            )),
+        }
+    }
+}
+
+fn request_error(e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(code, resp) => {
+            let message = resp
+                .into_json::<Value>()
+                .ok()
+                .and_then(|v| v["message"].as_str().map(String::from))
+                .unwrap_or_default();
+            format!("GitHub request failed: {} {}", code, message)
+                .trim_end()
+                .to_string()
+        }
+        e => format!("GitHub request failed: {}", e),
+    }
+}
+
+fn error_messages(body: &Value) -> String {
+    let messages: Vec<&str> = body["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e["message"].as_str())
+        .collect();
+    if messages.is_empty() {
+        "no data in response".to_string()
+    } else {
+        messages.join("; ")
+    }
+}
+
+/// A one-shot HTTP server for tests: answers each of the next
+/// `responses.len()` connections with the given JSON body, and sends back
+/// the raw requests it received.
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    pub struct Request {
+        pub head: String,
+        pub body: String,
+    }
+
+    pub fn serve(responses: Vec<(u16, String)>) -> (String, mpsc::Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let Ok((stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                let length = head
+                    .lines()
+                    .find_map(|l| {
+                        let (name, value) = l.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                let mut request_body = vec![0; length];
+                reader.read_exact(&mut request_body).unwrap();
+                let _ = tx.send(Request {
+                    head,
+                    body: String::from_utf8_lossy(&request_body).to_string(),
+                });
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = reader.get_mut().write_all(response.as_bytes());
+            }
+        });
+        (url, rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphql_url_for_github_and_enterprise() {
+        let url = |base: &str| Client::new(base, None).graphql_url();
+        assert_eq!(
+            url("https://api.github.com"),
+            "https://api.github.com/graphql"
+        );
+        assert_eq!(
+            url("https://ghe.example.com/api/v3/"),
+            "https://ghe.example.com/api/graphql"
+        );
+    }
+
+    #[test]
+    fn test_get_sends_token_and_maps_not_found() {
+        let (url, requests) = mock::serve(vec![
+            (200, r#"{"full_name": "o/r"}"#.into()),
+            (404, r#"{"message": "Not Found"}"#.into()),
+            (401, r#"{"message": "Bad credentials"}"#.into()),
+        ]);
+        let client = Client::new(&url, Some("secret".into()));
+
+        let repo: Option<Value> = client.get("repos/o/r").unwrap();
+        assert_eq!(repo.unwrap()["full_name"], "o/r");
+        let request = requests.recv().unwrap();
+        assert!(request.head.starts_with("GET /repos/o/r HTTP/1.1"));
+        assert!(request.head.contains("Bearer secret"));
+
+        assert!(client.get::<Value>("repos/o/missing").unwrap().is_none());
+        assert_eq!(
+            client.get::<Value>("user").unwrap_err(),
+            "GitHub request failed: 401 Bad credentials"
+        );
+    }
+
+    #[test]
+    fn test_graphql_returns_data_or_errors() {
+        let (url, requests) = mock::serve(vec![
+            (200, r#"{"data": {"viewer": {"login": "me"}}}"#.into()),
+            (200, r#"{"errors": [{"message": "Parse error"}]}"#.into()),
+        ]);
+        let client = Client::new(&url, Some("secret".into()));
+
+        let data = client.graphql("query { viewer { login } }").unwrap();
+        assert_eq!(data["viewer"]["login"], "me");
+        let request = requests.recv().unwrap();
+        assert!(request.head.starts_with("POST /graphql HTTP/1.1"));
+        assert_eq!(
+            serde_json::from_str::<Value>(&request.body).unwrap()["query"],
+            "query { viewer { login } }"
+        );
+
+        assert_eq!(
+            client.graphql("query {").unwrap_err(),
+            "GitHub GraphQL query failed: Parse error"
+        );
+        assert!(Client::new(&url, None).graphql("query {}").is_err());
+    }
+}
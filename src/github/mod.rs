@@ -3,6 +3,12 @@ use serde::Deserialize;
 use std::collections::HashMap;
 use std::path::Path;
 use std::process::{Command, Output, Stdio};
+
+mod batch;
+mod client;
+
+pub use batch::{task_statuses, TaskQuery};
+pub use client::client;
 use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
@@ -14,16 +20,72 @@ pub struct PrStatus {
     #[serde(rename = "isDraft")]
     pub is_draft: bool,
     pub url: String,
-    #[serde(default, skip_deserializing)]
+    #[serde(default)]
     pub comments: Vec<CommentCount>,
+    /// The branch the PR merges into
+    #[serde(default)]
+    pub base: Option<String>,
+    /// Rollup of the CI checks on the head commit, if it has any
+    #[serde(default)]
+    pub checks: Option<Checks>,
+    /// APPROVED, CHANGES_REQUESTED or REVIEW_REQUIRED; None if the base
+    /// branch doesn't require reviews
+    #[serde(default)]
+    pub review_decision: Option<String>,
+    /// Users and teams whose review is requested
+    #[serde(default)]
+    pub requested_reviewers: Vec<String>,
+    /// Requested reviewers beyond the first page
+    #[serde(default)]
+    pub unlisted_reviewers: usize,
+    /// MERGEABLE, CONFLICTING or UNKNOWN (while GitHub computes it)
+    #[serde(default)]
+    pub mergeable: Option<String>,
 }
 
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, Deserialize, serde::Serialize)]
 pub struct CommentCount {
     pub author: String,
     pub count: usize,
 }
 
+#[derive(Clone, Debug, Deserialize, serde::Serialize)]
+pub struct Checks {
+    pub state: CheckState,
+    /// Names of the failed checks
+    pub failing: Vec<String>,
+    /// Checks beyond the first page, which may have failed too
+    #[serde(default)]
+    pub unlisted: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckState {
+    Pass,
+    Fail,
+    Pending,
+}
+
+impl CheckState {
+    /// From a GraphQL `StatusState`.
+    fn from_rollup(state: &str) -> Self {
+        match state {
+            "SUCCESS" => CheckState::Pass,
+            "FAILURE" | "ERROR" => CheckState::Fail,
+            _ => CheckState::Pending,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckState::Pass => "pass",
+            CheckState::Fail => "fail",
+            CheckState::Pending => "pending",
+        }
+    }
+}
+
 impl PrStatus {
     pub fn display(&self) -> String {
         let state = if self.is_draft {
@@ -50,66 +112,91 @@ impl PrStatus {
             .collect();
         Some(parts.join(" "))
     }
-}
 
-pub fn get_pr_status(project_path: &Path) -> Option<PrStatus> {
-    let output = Command::new("gh")
-        .args(["pr", "view", "--json", "number,state,isDraft,url"])
-        .current_dir(project_path)
-        .output()
-        .ok()?;
+    fn is_open(&self) -> bool {
+        self.state == "OPEN"
+    }
 
-    if !output.status.success() {
-        return None;
+    /// E.g. `✗ failing: lint, test`, plus e.g. ` (120 checks not listed)` if
+    /// there were too many to fetch; None once the PR is merged or closed.
+    pub fn checks_display(&self) -> Option<String> {
+        let checks = self.checks.as_ref().filter(|_| self.is_open())?;
+        let unlisted = match checks.unlisted {
+            0 => String::new(),
+            1 => " (1 check not listed)".to_string(),
+            n => format!(" ({} checks not listed)", n),
+        };
+        Some(match checks.state {
+            CheckState::Pass => "✓ passing".to_string(),
+            CheckState::Pending => "● pending".to_string(),
+            CheckState::Fail if checks.failing.is_empty() => format!("✗ failing{}", unlisted),
+            CheckState::Fail => format!("✗ failing: {}{}", checks.failing.join(", "), unlisted),
+        })
     }
 
-    let mut pr: PrStatus = serde_json::from_slice(&output.stdout).ok()?;
-    pr.comments = get_pr_comments(project_path, pr.number);
-    Some(pr)
-}
+    /// The review decision in words, e.g. `changes requested`.
+    pub fn review_decision_display(&self) -> Option<&'static str> {
+        match self.review_decision.as_deref()? {
+            "APPROVED" => Some("approved"),
+            "CHANGES_REQUESTED" => Some("changes requested"),
+            "REVIEW_REQUIRED" => Some("review required"),
+            _ => None,
+        }
+    }
+
+    /// E.g. `changes requested; requested: alice, acme/core`; None once the
+    /// PR is merged or closed.
+    pub fn reviews_display(&self) -> Option<String> {
+        if !self.is_open() {
+            return None;
+        }
+        let more = match self.unlisted_reviewers {
+            0 => String::new(),
+            n => format!(" and {} more", n),
+        };
+        let requested = (!self.requested_reviewers.is_empty())
+            .then(|| format!("requested: {}{}", self.requested_reviewers.join(", "), more));
+        let parts: Vec<String> = self
+            .review_decision_display()
+            .map(String::from)
+            .into_iter()
+            .chain(requested)
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("; "))
+    }
 
-fn get_pr_comments(project_path: &Path, pr_number: u64) -> Vec<CommentCount> {
-    let output = Command::new("gh")
-        .args([
-            "api",
-            &format!("repos/{{owner}}/{{repo}}/pulls/{}/comments", pr_number),
-            "--jq",
-            ".[].user.login",
-        ])
-        .current_dir(project_path)
-        .output()
-        .ok();
-
-    let review_comments: Vec<String> = output
-        .filter(|o| o.status.success())
-        .and_then(|o| String::from_utf8(o.stdout).ok())
-        .map(|s| s.lines().map(String::from).collect())
-        .unwrap_or_default();
-
-    let output = Command::new("gh")
-        .args([
-            "api",
-            &format!("repos/{{owner}}/{{repo}}/issues/{}/comments", pr_number),
-            "--jq",
-            ".[].user.login",
-        ])
-        .current_dir(project_path)
-        .output()
-        .ok();
-
-    let issue_comments: Vec<String> = output
-        .filter(|o| o.status.success())
-        .and_then(|o| String::from_utf8(o.stdout).ok())
-        .map(|s| s.lines().map(String::from).collect())
-        .unwrap_or_default();
+    pub fn has_conflicts(&self) -> bool {
+        self.is_open() && self.mergeable.as_deref() == Some("CONFLICTING")
+    }
 
-    let mut counts: HashMap<String, usize> = HashMap::new();
-    for author in review_comments.into_iter().chain(issue_comments) {
-        *counts.entry(author).or_default() += 1;
+    /// E.g. `into main (conflicts)`; None once the PR is merged or closed.
+    pub fn merge_display(&self) -> Option<String> {
+        if !self.is_open() {
+            return None;
+        }
+        let base = self.base.as_deref().map(|b| format!("into {}", b));
+        let mergeable = match self.mergeable.as_deref() {
+            Some("CONFLICTING") => Some("conflicts"),
+            Some("MERGEABLE") => Some("mergeable"),
+            _ => None,
+        };
+        match (base, mergeable) {
+            (Some(base), Some(m)) => Some(format!("{} ({})", base, m)),
+            (Some(base), None) => Some(base),
+            (None, m) => m.map(String::from),
+        }
     }
+}
 
+/// Comment counts by author, most comments first.
+fn count_comments(comments: impl Iterator<Item = (String, usize)>) -> Vec<CommentCount> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (author, count) in comments {
+        *counts.entry(author).or_default() += count;
+    }
     let mut result: Vec<CommentCount> = counts
         .into_iter()
+        .filter(|(_, count)| *count > 0)
         .map(|(author, count)| CommentCount { author, count })
         .collect();
     result.sort_by(|a, b| b.count.cmp(&a.count).then(a.author.cmp(&b.author)));
@@ -118,27 +205,18 @@ fn get_pr_comments(project_path: &Path, pr_number: u64) -> Vec<CommentCount> {
 
 /// Get the head branch name for a PR given owner/repo/number
 pub fn get_pr_branch(owner: &str, repo: &str, pr_number: u64) -> Option<String> {
-    let output = Command::new("gh")
-        .args([
-            "api",
-            &format!("repos/{}/{}/pulls/{}", owner, repo, pr_number),
-            "--jq",
-            ".head.ref",
-        ])
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
-
-    let branch = String::from_utf8(output.stdout).ok()?;
-    let branch = branch.trim();
-    if branch.is_empty() {
-        None
-    } else {
-        Some(branch.to_string())
-    }
+    #[derive(Deserialize)]
+    struct Pull {
+        head: Head,
+    }
+    #[derive(Deserialize)]
+    struct Head {
+        #[serde(rename = "ref")]
+        branch: String,
+    }
+    let path = format!("repos/{}/{}/pulls/{}", owner, repo, pr_number);
+    let pull: Pull = client().get(&path).ok()??;
+    Some(pull.head.branch).filter(|b| !b.is_empty())
 }
 
 #[derive(Debug, Deserialize, serde::Serialize)]
@@ -157,26 +235,15 @@ pub struct SearchRepository {
 }
 
 pub fn search_review_requests() -> Result<Vec<ReviewRequest>, String> {
-    let output = Command::new("gh")
-        .args([
-            "search",
-            "prs",
-            "user-review-requested:@me",
-            "--state=open",
-            "--limit",
-            "100",
-            "--json",
-            "number,title,url,repository",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run gh: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("gh search prs failed: {}", stderr.trim()));
-    }
-
-    serde_json::from_slice(&output.stdout).map_err(|e| format!("Failed to parse gh output: {}", e))
+    let data = client().graphql(
+        "query {
+  search(query: \"is:pr is:open user-review-requested:@me\", type: ISSUE, first: 100) {
+    nodes { ... on PullRequest { number title url repository { name nameWithOwner } } }
+  }
+}",
+    )?;
+    serde_json::from_value(data["search"]["nodes"].clone())
+        .map_err(|e| format!("Failed to parse GitHub response: {}", e))
 }
 
 pub fn pr_checkout(
@@ -342,70 +409,23 @@ pub fn resolve_github_ref_kind(r: &GithubRef) -> Result<GithubRefKind, String> {
 }
 
 pub fn get_issue(owner: &str, repo: &str, number: u64) -> Result<GithubIssue, String> {
-    let output = Command::new("gh")
-        .args([
-            "issue",
-            "view",
-            &number.to_string(),
-            "--repo",
-            &format!("{}/{}", owner, repo),
-            "--json",
-            "number,title,url,body",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run gh: {}", e))?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("gh issue view failed: {}", stderr.trim()));
-    }
-    serde_json::from_slice(&output.stdout).map_err(|e| format!("Failed to parse gh output: {}", e))
-}
-
-/// Check whether the current `gh` user has submitted a review on a PR.
-pub fn has_my_review(owner: &str, repo: &str, pr_number: u64) -> bool {
-    let login = match gh_login() {
-        Some(l) => l,
-        None => return false,
-    };
-    let output = Command::new("gh")
-        .args([
-            "pr",
-            "view",
-            &pr_number.to_string(),
-            "--repo",
-            &format!("{}/{}", owner, repo),
-            "--json",
-            "reviews",
-            "--jq",
-            &format!(
-                "[.reviews[] | select(.author.login == \"{}\") | .state] | map(select(. != \"PENDING\" and . != \"DISMISSED\")) | length",
-                login
-            ),
-        ])
-        .output()
-        .ok();
-    output
-        .filter(|o| o.status.success())
-        .and_then(|o| String::from_utf8(o.stdout).ok())
-        .and_then(|s| s.trim().parse::<u64>().ok())
-        .is_some_and(|n| n > 0)
-}
-
-fn gh_login() -> Option<String> {
-    use std::sync::OnceLock;
-    static LOGIN: OnceLock<Option<String>> = OnceLock::new();
-    LOGIN
-        .get_or_init(|| {
-            Command::new("gh")
-                .args(["api", "user", "--jq", ".login"])
-                .output()
-                .ok()
-                .filter(|o| o.status.success())
-                .and_then(|o| String::from_utf8(o.stdout).ok())
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-        })
-        .clone()
+    #[derive(Deserialize)]
+    struct Issue {
+        number: u64,
+        title: String,
+        html_url: String,
+        body: Option<String>,
+    }
+    let path = format!("repos/{}/{}/issues/{}", owner, repo, number);
+    let issue: Issue = client()
+        .get(&path)?
+        .ok_or_else(|| format!("Issue #{} not found in {}/{}", number, owner, repo))?;
+    Ok(GithubIssue {
+        number: issue.number,
+        title: issue.title,
+        url: issue.html_url,
+        body: issue.body.unwrap_or_default(),
+    })
 }
 
 use crate::project::Project;
@@ -426,74 +446,39 @@ pub fn get_repo_name(project: &Project) -> Option<String> {
     fetch_repo_name(&project.working_tree())
 }
 
-/// Refresh GitHub info by fetching from gh CLI
-pub fn refresh_github_info(project: &mut Project) {
-    project.cached.github_pr = fetch_pr_number(&project.working_tree());
-    project.cached.github_repo = fetch_repo_name(&project.working_tree());
+/// The open PR number and `owner/repo` of the worktree at `project_path`, from
+/// the git remote and the GitHub API. Don't call this holding the projects lock.
+pub fn fetch_github_info(project_path: &Path) -> (Option<u64>, Option<String>) {
+    (fetch_pr_number(project_path), fetch_repo_name(project_path))
 }
 
+/// The number of the PR for the branch checked out at `project_path`.
 fn fetch_pr_number(project_path: &Path) -> Option<u64> {
-    let output = Command::new("gh")
-        .args(["pr", "view", "--json", "number", "--jq", ".number"])
-        .current_dir(project_path)
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
-
-    String::from_utf8(output.stdout).ok()?.trim().parse().ok()
+    let query = TaskQuery {
+        repo: fetch_repo_name(project_path),
+        branch: crate::git::current_branch(project_path),
+        review_pr: None,
+    };
+    let statuses = task_statuses(client(), &[query]).ok()?;
+    statuses.into_iter().next()?.pr.map(|pr| pr.number)
 }
 
 /// If `owner/repo` is a fork, return the parent's `owner/repo`.
 pub fn get_parent_repo(owner: &str, repo: &str) -> Option<String> {
-    let output = Command::new("gh")
-        .args([
-            "api",
-            &format!("repos/{}/{}", owner, repo),
-            "--jq",
-            ".parent.full_name",
-        ])
-        .output()
-        .ok()?;
-    if !output.status.success() {
-        return None;
+    #[derive(Deserialize)]
+    struct Repo {
+        parent: Option<Parent>,
     }
-    let name = String::from_utf8(output.stdout).ok()?;
-    let name = name.trim();
-    if name.is_empty() || name == "null" {
-        None
-    } else {
-        Some(name.to_string())
+    #[derive(Deserialize)]
+    struct Parent {
+        full_name: String,
     }
+    let repo: Repo = client().get(&format!("repos/{}/{}", owner, repo)).ok()??;
+    repo.parent.map(|p| p.full_name)
 }
 
 fn fetch_repo_name(project_path: &Path) -> Option<String> {
-    let output = Command::new("gh")
-        .args([
-            "repo",
-            "view",
-            "--json",
-            "nameWithOwner",
-            "--jq",
-            ".nameWithOwner",
-        ])
-        .current_dir(project_path)
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
-    }
-
-    let name = String::from_utf8(output.stdout).ok()?;
-    let name = name.trim();
-    if name.is_empty() {
-        None
-    } else {
-        Some(name.to_string())
-    }
+    crate::git::github_repo_from_remote(project_path)
 }
 
 #[cfg(test)]
@@ -547,6 +532,94 @@ mod tests {
         assert_eq!(r.kind, Some(GithubRefKind::Issue));
     }
 
+    fn pr(state: &str) -> PrStatus {
+        PrStatus {
+            number: 12,
+            state: state.to_string(),
+            is_draft: false,
+            url: String::new(),
+            comments: vec![],
+            base: Some("main".into()),
+            checks: Some(Checks {
+                state: CheckState::Fail,
+                failing: vec!["lint".into(), "test".into()],
+                unlisted: 0,
+            }),
+            review_decision: Some("CHANGES_REQUESTED".into()),
+            requested_reviewers: vec!["alice".into(), "acme/core".into()],
+            unlisted_reviewers: 0,
+            mergeable: Some("MERGEABLE".into()),
+        }
+    }
+
+    #[test]
+    fn pr_status_details_display() {
+        let open = pr("OPEN");
+        assert_eq!(
+            open.checks_display().as_deref(),
+            Some("✗ failing: lint, test")
+        );
+        assert_eq!(
+            open.reviews_display().as_deref(),
+            Some("changes requested; requested: alice, acme/core")
+        );
+        assert_eq!(
+            open.merge_display().as_deref(),
+            Some("into main (mergeable)")
+        );
+        assert!(!open.has_conflicts());
+
+        let pending = PrStatus {
+            checks: Some(Checks {
+                state: CheckState::Pending,
+                failing: vec![],
+                unlisted: 0,
+            }),
+            review_decision: None,
+            requested_reviewers: vec![],
+            mergeable: Some("UNKNOWN".into()),
+            ..open
+        };
+        assert_eq!(pending.checks_display().as_deref(), Some("● pending"));
+        assert_eq!(pending.reviews_display(), None);
+        assert_eq!(pending.merge_display().as_deref(), Some("into main"));
+
+        // Checks and reviewers beyond the first page are counted
+        let many = PrStatus {
+            checks: Some(Checks {
+                state: CheckState::Fail,
+                failing: vec!["lint".into()],
+                unlisted: 20,
+            }),
+            unlisted_reviewers: 3,
+            ..pr("OPEN")
+        };
+        assert_eq!(
+            many.checks_display().as_deref(),
+            Some("✗ failing: lint (20 checks not listed)")
+        );
+        assert_eq!(
+            many.reviews_display().as_deref(),
+            Some("changes requested; requested: alice, acme/core and 3 more")
+        );
+
+        let merged = pr("MERGED");
+        assert_eq!(merged.checks_display(), None);
+        assert_eq!(merged.reviews_display(), None);
+        assert_eq!(merged.merge_display(), None);
+    }
+
+    #[test]
+    fn pr_status_deserializes_without_details() {
+        let pr: PrStatus = serde_json::from_str(
+            r#"{"number": 3, "state": "OPEN", "isDraft": false, "url": "u",
+                "comments": [{"author": "bob", "count": 2}]}"#,
+        )
+        .unwrap();
+        assert_eq!(pr.comments_display().as_deref(), Some("bob:2"));
+        assert!(pr.checks.is_none() && pr.requested_reviewers.is_empty());
+    }
+
     #[test]
     fn output_with_timeout_returns_output() {
         let mut cmd = Command::new("sh");
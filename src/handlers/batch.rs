@@ -82,6 +82,53 @@ pub fn cancel(id: &str) -> Response<Body> {
     }
 }
 
+/// The grouped report as JSON, or with `format=html` as a page for browsers.
+pub fn batch_report(id: &str, format: Option<&str>) -> Response<Body> {
+    let store = batch::lock();
+    let Some(batch) = store.get(id) else {
+        return error_response(StatusCode::NOT_FOUND, "batch not found");
+    };
+    let report = batch.to_response().report();
+    match format {
+        Some("html") => {
+            let esc = crate::handlers::dashboard::html_escape;
+            let html = format!(
+                "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Batch {}</title></head>\n\
+                 <body><h1>{}</h1><pre>{}</pre></body></html>\n",
+                esc(&report.id),
+                esc(&report.command.join(" ")),
+                esc(&report.render_terminal())
+            );
+            Response::builder()
+                .header("Content-Type", "text/html; charset=utf-8")
+                .body(Body::from(html))
+                .unwrap()
+        }
+        _ => json_response(StatusCode::OK, &report),
+    }
+}
+
+pub fn list_schedules() -> Response<Body> {
+    json_response(StatusCode::OK, &crate::schedule::list())
+}
+
+pub fn run_schedule(name: &str) -> Response<Body> {
+    let entry = crate::config::reload_schedules()
+        .into_iter()
+        .find(|s| s.name == name);
+    let Some(entry) = entry else {
+        return error_response(StatusCode::NOT_FOUND, "schedule not found");
+    };
+    let Some(id) = crate::schedule::fire(&entry) else {
+        return error_response(StatusCode::BAD_REQUEST, "no projects match schedule");
+    };
+    let store = batch::lock();
+    match store.get(&id) {
+        Some(batch) => json_response(StatusCode::CREATED, &batch.to_response()),
+        None => error_response(StatusCode::NOT_FOUND, "batch not found"),
+    }
+}
+
 pub fn batch_output(id: &str, run_idx: Option<usize>, offset: Option<u64>) -> Response<Body> {
     let run_idx = run_idx.unwrap_or(0);
     let offset = offset.unwrap_or(0);
@@ -45,7 +45,9 @@ pub fn dashboard() -> Response<Body> {
     }
 
     let template = include_str!("dashboard.html");
-    let html = template.replace("{{CARDS}}", &cards_html);
+    let html = template
+        .replace("{{SCHEDULE_FAILURES}}", &render_schedule_failures())
+        .replace("{{CARDS}}", &cards_html);
 
     Response::builder()
         .header("Content-Type", "text/html; charset=utf-8")
@@ -70,8 +72,8 @@ fn render_task_card(
             )
         })
         .unwrap_or_default();
-    let is_review = task.kv.get("task_type").is_some_and(|v| v == "review");
-    let review_submitted = task.kv.get("review_submitted").is_some_and(|v| v == "true");
+    let is_review = task.kv_str("task_type") == Some("review");
+    let review_submitted = task.kv_flag("review_submitted");
     let review_badge = if is_review {
         if review_submitted {
             r#" <span class="card-review-badge reviewed" title="Review submitted">&#x1F50D;</span>"#
@@ -114,16 +116,16 @@ fn render_task_card(
             .map(|c| format!(" [{}]", html_escape(&c)))
             .unwrap_or_default();
         format!(
-            r#"<span class="meta-item"><a href="{}" target="_blank">{}</a>{}</span>"#,
+            r#"<span class="meta-item"><a href="{}" target="_blank">{}</a>{}</span>{}"#,
             pr.url,
             html_escape(&pr.display()),
-            comments
+            comments,
+            render_pr_details(pr)
         )
-    } else if let Some(url) = task.kv.get("review_pr_url") {
+    } else if let Some(url) = task.kv_str("review_pr_url") {
         let label = task
-            .kv
-            .get("review_pr_title")
-            .map(|t| html_escape(t))
+            .kv_str("review_pr_title")
+            .map(html_escape)
             .unwrap_or_else(|| "PR".to_string());
         format!(
             r#"<span class="meta-item"><a href="{}" target="_blank">{}</a></span>"#,
@@ -195,6 +197,8 @@ fn render_task_card(
         String::new()
     };
 
+    let conversations_html = render_conversations(&task_key);
+
     let iframe_html = render_iframe(task);
 
     let status_attr = task
@@ -242,7 +246,7 @@ fn render_task_card(
         r#"<div class="card{}" data-task="{}"{}{}{}{}>
 <div class="card-header">{}<span class="card-summary">{}</span>{}{}</div>
 <div class="card-meta">{}{}{}{}</div>
-{}{}
+{}{}{}
 </div>"#,
         current_class,
         html_escape(&task_id),
@@ -259,10 +263,119 @@ fn render_task_card(
         pr_html,
         assignee_html,
         card_content_html,
+        conversations_html,
         iframe_html
     )
 }
 
+/// Badges for an open PR's checks, review decision and merge state; the
+/// details (failing checks, requested reviewers, base branch) are in titles.
+fn render_pr_details(pr: &crate::github::PrStatus) -> String {
+    let mut html = String::new();
+    if let (Some(checks), Some(text)) = (&pr.checks, pr.checks_display()) {
+        let label = match checks.state {
+            crate::github::CheckState::Fail if !checks.failing.is_empty() => {
+                let more = if checks.unlisted > 0 { "+" } else { "" };
+                format!("✗ {}{} failing", checks.failing.len(), more)
+            }
+            _ => text.clone(),
+        };
+        html += &format!(
+            r#"<span class="meta-item pr-checks pr-checks-{}" title="{}">{}</span>"#,
+            checks.state.as_str(),
+            html_escape(&text),
+            html_escape(&label)
+        );
+    }
+    if let Some(reviews) = pr.reviews_display() {
+        let decision = pr.review_decision_display().unwrap_or("review requested");
+        html += &format!(
+            r#"<span class="meta-item pr-review pr-review-{}" title="{}">{}</span>"#,
+            decision.replace(' ', "-"),
+            html_escape(&reviews),
+            decision
+        );
+    }
+    if let Some(merge) = pr.merge_display() {
+        if pr.has_conflicts() {
+            html += &format!(
+                r#"<span class="meta-item pr-conflicts" title="{}">conflicts</span>"#,
+                html_escape(&merge)
+            );
+        } else if let Some(ref base) = pr.base {
+            html += &format!(
+                r#"<span class="meta-item pr-base" title="{}">→ {}</span>"#,
+                html_escape(&merge),
+                html_escape(base)
+            );
+        }
+    }
+    html
+}
+
+/// The task's synced agent sessions, each with a button to resume it.
+fn render_conversations(task_key: &str) -> String {
+    let sessions = crate::conversations::timeline::task_sessions(task_key);
+    if sessions.is_empty() {
+        return String::new();
+    }
+    let items: String = sessions
+        .iter()
+        .map(|s| {
+            let source = s
+                .source
+                .as_deref()
+                .map(|src| format!(" · {}", html_escape(src)))
+                .unwrap_or_default();
+            let resume_url = crate::conversations::resume_path(&s.path);
+            format!(
+                concat!(
+                    r#"<li><span class="conversation-date">{}</span> "#,
+                    r#"<span class="conversation-title" title="{}">{}</span> "#,
+                    r#"<span class="conversation-meta">{} messages{}</span> "#,
+                    r#"<button class="btn btn-resume" data-url="{}">Resume</button></li>"#,
+                ),
+                html_escape(&s.date),
+                html_escape(&s.path),
+                html_escape(&s.title),
+                s.messages,
+                source,
+                html_escape(&resume_url),
+            )
+        })
+        .collect();
+    format!(
+        r#"<details class="card-conversations"><summary>{} conversation{}</summary><ul>{}</ul></details>"#,
+        sessions.len(),
+        if sessions.len() == 1 { "" } else { "s" },
+        items
+    )
+}
+
+fn render_schedule_failures() -> String {
+    let failing: String = crate::schedule::list()
+        .schedules
+        .iter()
+        .filter(|s| s.is_failing())
+        .filter_map(|s| {
+            let b = s.batches.iter().find(|b| b.done)?;
+            Some(format!(
+                r#"<div class="schedule-failure">&#x26A0;&#xFE0F; Scheduled <b>{}</b> failed in {}/{} projects (<a href="/batch/{}/report?format=html" target="_blank">{}</a>)</div>"#,
+                html_escape(&s.entry.name),
+                b.failed,
+                b.total,
+                html_escape(&b.id),
+                html_escape(&b.id)
+            ))
+        })
+        .collect();
+    if failing.is_empty() {
+        String::new()
+    } else {
+        format!(r#"<div class="schedule-failures">{}</div>"#, failing)
+    }
+}
+
 fn render_project_card(project: &crate::project::Project) -> String {
     let name = html_escape(project.repo_name.as_str());
     let key = html_escape(&project.store_key().to_string());
@@ -369,7 +482,7 @@ fn claude_md_button(path: &std::path::Path) -> String {
     }
 }
 
-fn html_escape(s: &str) -> String {
+pub(crate) fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
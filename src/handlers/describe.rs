@@ -207,7 +207,7 @@ fn describe_jira(jira_key: &str) -> DescribeResponse {
     let tasks = projects::tasks();
     let project = tasks
         .values()
-        .find(|p| p.kv.get("jira_key").is_some_and(|k| k == jira_key));
+        .find(|p| p.kv_str("jira_key") == Some(jira_key));
 
     if let Some(project) = project {
         let pr_number = github::get_open_pr_number(project);
@@ -217,7 +217,12 @@ fn describe_jira(jira_key: &str) -> DescribeResponse {
             (Some(pr), Some(repo)) => {
                 let short_repo = repo.split('/').next_back().unwrap_or(&repo);
                 (
-                    Some(format!("https://github.com/{}/pull/{}", repo, pr)),
+                    Some(format!(
+                        "https://{}/{}/pull/{}",
+                        crate::config::github_host(),
+                        repo,
+                        pr
+                    )),
                     Some(format!("{}#{}", short_repo, pr)),
                 )
             }
@@ -226,7 +231,7 @@ fn describe_jira(jira_key: &str) -> DescribeResponse {
 
         let jira_url = jira_url_for_key(jira_key);
         let name = project.store_key().to_string();
-        let task_type = project.kv.get("task_type").cloned();
+        let task_type = project.kv_str("task_type").map(String::from);
 
         DescribeResponse {
             name: Some(name),
@@ -259,7 +264,7 @@ fn describe_jira(jira_key: &str) -> DescribeResponse {
     }
 }
 
-fn jira_url_for_key(key: &str) -> Option<String> {
+pub(crate) fn jira_url_for_key(key: &str) -> Option<String> {
     // Check if key looks like a JIRA key (e.g., "ACT-708", "PROJ-123")
     let jira_key_re = Regex::new(r"^[A-Z]+-\d+").ok()?;
     if !jira_key_re.is_match(key) {
@@ -287,8 +292,8 @@ fn find_task_by_pr(owner: &str, repo: &str, pr_number: u64) -> Option<TaskMatch>
         Some(TaskMatch {
             store_key: key.clone(),
             home: project.repo_name.to_string(),
-            jira_key: project.kv.get("jira_key").cloned(),
-            task_type: project.kv.get("task_type").cloned(),
+            jira_key: project.kv_str("jira_key").map(String::from),
+            task_type: project.kv_str("task_type").map(String::from),
         })
     })
 }
@@ -297,7 +302,7 @@ fn find_task_by_pr(owner: &str, repo: &str, pr_number: u64) -> Option<TaskMatch>
 /// First checks the stored review_pr_url KV (fast, covers fork PRs),
 /// then falls back to `gh pr view` (slow, only finds same-repo PRs).
 fn matches_pr(project: &crate::project::Project, expected_repo: &str, pr_number: u64) -> bool {
-    if let Some(url) = project.kv.get("review_pr_url") {
+    if let Some(url) = project.kv_str("review_pr_url") {
         if let Some(gh) = parse_github_url(url) {
             if gh.pr == Some(pr_number) && format!("{}/{}", gh.owner, gh.repo) == expected_repo {
                 return true;
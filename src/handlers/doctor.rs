@@ -141,7 +141,16 @@ pub struct ProjectPersistedData {
     pub name: String,
     pub path: String,
     pub worktrees: Vec<WorktreeInfo>,
-    pub kv: HashMap<String, HashMap<String, String>>,
+    pub kv: HashMap<String, crate::kv::KvMap>,
+    /// KV files that fail to parse, and backups of such files
+    #[serde(default)]
+    pub corrupt_kv: Vec<CorruptKvFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CorruptKvFile {
+    pub file: String,
+    pub error: String,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -175,10 +184,16 @@ impl PersistedDataReport {
                 for (file, pairs) in &project.kv {
                     lines.push(format!("    {}:", file));
                     for (k, v) in pairs {
-                        lines.push(format!("      {}: {}", k, v));
+                        lines.push(format!("      {}: {}", k, crate::kv::value_to_string(v)));
                     }
                 }
             }
+            if !project.corrupt_kv.is_empty() {
+                lines.push("  corrupt kv:".to_string());
+                for c in &project.corrupt_kv {
+                    lines.push(format!("    {}: {}", c.file, c.error));
+                }
+            }
             lines.push(String::new());
         }
         lines.join("\n")
@@ -214,28 +229,41 @@ pub fn persisted_data() -> Response<Body> {
                 .collect();
 
             let kv_dir = git::git_common_dir(path).join("wormhole/kv");
-            let mut all_kv: HashMap<String, HashMap<String, String>> = HashMap::new();
+            let mut all_kv: HashMap<String, crate::kv::KvMap> = HashMap::new();
+            let mut corrupt_kv = Vec::new();
             if let Ok(entries) = fs::read_dir(&kv_dir) {
                 for entry in entries.flatten() {
                     let file_path = entry.path();
-                    if file_path.extension().map(|e| e == "json").unwrap_or(false) {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    if file_name.ends_with(".json.corrupt") {
+                        corrupt_kv.push(CorruptKvFile {
+                            file: file_name,
+                            error: "backup of a corrupt KV file; merge by hand and delete"
+                                .to_string(),
+                        });
+                    } else if file_path.extension().map(|e| e == "json").unwrap_or(false) {
                         if let Ok(contents) = fs::read_to_string(&file_path) {
-                            if let Ok(kv) =
-                                serde_json::from_str::<HashMap<String, String>>(&contents)
-                            {
-                                let stem = file_path
-                                    .file_stem()
-                                    .and_then(|s| s.to_str())
-                                    .unwrap_or("unknown")
-                                    .to_string();
-                                all_kv.insert(stem, kv);
+                            match serde_json::from_str::<crate::kv::KvMap>(&contents) {
+                                Ok(kv) => {
+                                    let stem = file_path
+                                        .file_stem()
+                                        .and_then(|s| s.to_str())
+                                        .unwrap_or("unknown")
+                                        .to_string();
+                                    all_kv.insert(stem, kv);
+                                }
+                                Err(e) => corrupt_kv.push(CorruptKvFile {
+                                    file: file_name,
+                                    error: e.to_string(),
+                                }),
                             }
                         }
                     }
                 }
             }
+            corrupt_kv.sort_by(|a, b| a.file.cmp(&b.file));
 
-            if wormhole_worktrees.is_empty() && all_kv.is_empty() {
+            if wormhole_worktrees.is_empty() && all_kv.is_empty() && corrupt_kv.is_empty() {
                 return None;
             }
 
@@ -244,6 +272,7 @@ pub fn persisted_data() -> Response<Body> {
                 path: path.display().to_string(),
                 worktrees: wormhole_worktrees,
                 kv: all_kv,
+                corrupt_kv,
             })
         })
         .collect();
@@ -579,6 +608,19 @@ fn parse_workspace_name(title: &str) -> Option<&str> {
     Some(ws)
 }
 
+fn json_response<T: Serialize>(value: &T) -> Response<Body> {
+    match serde_json::to_string(value) {
+        Ok(json) => Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(json))
+            .unwrap(),
+        Err(e) => Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(format!("Failed to serialize: {}", e)))
+            .unwrap(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -668,16 +710,3 @@ mod tests {
         assert!(!lines[2].contains("stranded"));
     }
 }
-
-fn json_response<T: Serialize>(value: &T) -> Response<Body> {
-    match serde_json::to_string(value) {
-        Ok(json) => Response::builder()
-            .header("Content-Type", "application/json")
-            .body(Body::from(json))
-            .unwrap(),
-        Err(e) => Response::builder()
-            .status(StatusCode::INTERNAL_SERVER_ERROR)
-            .body(Body::from(format!("Failed to serialize: {}", e)))
-            .unwrap(),
-    }
-}
@@ -29,7 +29,7 @@ pub fn sprint_list() -> Response<Body> {
     let mut tasks: Vec<serde_json::Value> = open_projects
         .into_iter()
         .filter_map(|project| {
-            let jira_key = project.kv.get("jira_key")?;
+            let jira_key = project.kv_str("jira_key")?;
             if !sprint_keys.contains(jira_key) {
                 return None;
             }
@@ -71,7 +71,7 @@ pub fn sprint_list() -> Response<Body> {
 #[serde(tag = "type")]
 pub enum SprintShowItem {
     #[serde(rename = "task")]
-    Task(TaskStatus),
+    Task(Box<TaskStatus>),
     #[serde(rename = "issue")]
     Issue(IssueStatus),
 }
@@ -95,7 +95,7 @@ pub fn sprint_show() -> Response<Body> {
     let items: Vec<SprintShowItem> = issues
         .into_iter()
         .map(|issue| match status::get_status_by_name(&issue.key) {
-            Some(task) => SprintShowItem::Task(task),
+            Some(task) => SprintShowItem::Task(Box::new(task)),
             None => SprintShowItem::Issue(issue),
         })
         .collect();
@@ -9,13 +9,29 @@ use std::time::{Duration, Instant};
 use crate::project::ProjectKey;
 use crate::project_path::ProjectPath;
 use crate::projects::Mutation;
+use crate::selector::{self, Selector};
 use crate::wormhole::QueryParams;
 use crate::{config, hammerspoon, projects, util::debug};
 
 /// Return JSON with current and available projects (including tasks)
 /// Includes cached JIRA/PR status for tasks
 /// If active=true, only returns projects with tmux windows
-pub fn list_projects(active_only: bool, tasks_only: bool, with_editor: bool) -> Response<Body> {
+/// If `selector` is given, only returns projects matching that `--where` expression
+pub fn list_projects(
+    active_only: bool,
+    tasks_only: bool,
+    with_editor: bool,
+    selector: Option<&str>,
+) -> Response<Body> {
+    let selector = match selector.map(Selector::parse).transpose() {
+        Ok(s) => s,
+        Err(e) => {
+            return Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(format!("Invalid --where expression: {}", e)))
+                .unwrap()
+        }
+    };
     let mut open_projects = if active_only {
         let window_names = crate::tmux::window_names();
         projects::lock()
@@ -33,6 +49,16 @@ pub fn list_projects(active_only: bool, tasks_only: bool, with_editor: bool) ->
         let editor_projects = crate::messages::lock().projects_with_role("editor");
         open_projects.retain(|p| editor_projects.contains(&p.store_key().to_string()));
     }
+    if let Some(selector) = selector {
+        let ctx = selector::Context {
+            window_names: if selector.uses_field(&selector::Field::Active) {
+                crate::tmux::window_names()
+            } else {
+                vec![]
+            },
+        };
+        open_projects.retain(|p| selector.matches(p, &ctx));
+    }
 
     let current: Vec<_> = open_projects
         .into_iter()
@@ -170,7 +196,7 @@ pub fn pin_current() {
         let app = hammerspoon::current_application();
         let key = current.store_key();
         drop(projects); // Release lock before modifying KV
-        crate::kv::set_value_sync(&key, "land-in", app.as_str());
+        crate::kv::set_value_sync(&key, "pin", "land-in", app.as_str());
         hammerspoon::alert("📌");
         if debug() {
             crate::ps!("Pinned {}: land-in={}", key, app.as_str());
@@ -235,7 +261,7 @@ pub fn navigate(direction: Direction, params: &QueryParams) {
                 Direction::Next => projects.next(),
             };
             if let Some(ref p) = candidate {
-                projects.apply(mutation.clone(), &p.store_key());
+                projects.apply(mutation, &p.store_key());
                 let excluded = active_keys
                     .as_ref()
                     .is_some_and(|active| !active.contains(&p.store_key().to_string()));
@@ -339,9 +365,13 @@ pub fn show(name: Option<&str>) -> Response<Body> {
 
 pub fn refresh_project(name: &str) -> Response<Body> {
     let key = ProjectKey::parse(name.trim());
+    // The GitHub API call can take a while, so make it without the lock
+    let working_tree = projects::lock().by_key(&key).map(|p| p.working_tree());
+    let info = working_tree.map(|wt| crate::github::fetch_github_info(&wt));
     let mut projects = projects::lock();
-    if let Some(project) = projects.get_mut(&key) {
-        crate::github::refresh_github_info(project);
+    if let (Some((pr, repo)), Some(project)) = (info, projects.get_mut(&key)) {
+        project.cached.github_pr = pr;
+        project.cached.github_repo = repo;
         let json = serde_json::json!({
             "project_key": project.store_key().to_string(),
             "github_pr": project.cached.github_pr,
@@ -0,0 +1,517 @@
+use hyper::{Body, Request, Response, StatusCode};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::scope::{self, ScopeTarget};
+use super::KvMap;
+use crate::config;
+use crate::project::{BranchName, Project};
+use crate::projects;
+
+/*
+    - A bundle holds the KV of every project, task, repo scope and the global
+      scope, keyed by GitHub repo (`owner/name`) and branch rather than by
+      local path, so it can be imported on another machine.
+    - Import matches each repo by GitHub origin, falling back to the local
+      project name. Keys whose local value differs are reported as conflicts
+      and left alone unless `overwrite` is set.
+    - Tasks need not exist yet: their KV is written to the repo's KV dir and
+      picked up when the task is created.
+    - TOML has no null, so null values are dropped from TOML exports.
+    - A target whose KV can't be written is reported as failed, and the
+      import carries on with the rest.
+*/
+
+/// The bundle format version written by `export` and accepted by `import`.
+pub const VERSION: u32 = 1;
+
+pub type Entries = BTreeMap<String, Value>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Bundle {
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub global: Entries,
+    #[serde(default)]
+    pub repos: Vec<RepoBundle>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RepoBundle {
+    /// `owner/name` of the repo's GitHub origin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github: Option<String>,
+    /// Project name on the exporting machine
+    pub name: String,
+    /// Repo scope
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scope: Entries,
+    /// The repo's main project
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub project: Entries,
+    /// Branch -> task KV
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tasks: BTreeMap<String, Entries>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// Keys written (or that would be written)
+    pub imported: usize,
+    /// Keys whose local value already matched
+    pub unchanged: usize,
+    /// Keys left alone because the local value differs
+    pub conflicts: Vec<Conflict>,
+    /// Keys skipped because their value fails validation (see `schema`)
+    #[serde(default)]
+    pub invalid: Vec<Invalid>,
+    /// Bundle repos with no matching local project
+    pub unmatched: Vec<String>,
+    /// Targets whose KV couldn't be written; their keys aren't counted as
+    /// imported
+    #[serde(default)]
+    pub failed: Vec<Failure>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Conflict {
+    /// `@global`, `@<repo>`, `<repo>` or `<repo>:<branch>`
+    pub target: String,
+    pub key: String,
+    pub local: Value,
+    pub incoming: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Failure {
+    pub target: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Invalid {
+    pub target: String,
+    pub key: String,
+    pub error: String,
+}
+
+impl ImportReport {
+    /// Record that writing `target` failed, taking back the keys counted as
+    /// imported since the count was `imported`.
+    fn failed(&mut self, target: &str, imported: usize, error: String) {
+        self.imported = imported;
+        self.failed.push(Failure {
+            target: target.to_string(),
+            error,
+        });
+    }
+}
+
+impl Bundle {
+    /// Remove null values (including within arrays and objects), which TOML
+    /// can't represent. Returns how many were removed.
+    pub fn strip_nulls(&mut self) -> usize {
+        let repos = self.repos.iter_mut().flat_map(|r| {
+            [&mut r.scope, &mut r.project]
+                .into_iter()
+                .chain(r.tasks.values_mut())
+        });
+        std::iter::once(&mut self.global)
+            .chain(repos)
+            .map(strip_null_entries)
+            .sum()
+    }
+}
+
+fn strip_null_entries(entries: &mut Entries) -> usize {
+    let before = entries.len();
+    entries.retain(|_, v| !v.is_null());
+    before - entries.len() + entries.values_mut().map(strip_null_values).sum::<usize>()
+}
+
+fn strip_null_values(value: &mut Value) -> usize {
+    match value {
+        Value::Array(items) => {
+            let before = items.len();
+            items.retain(|v| !v.is_null());
+            before - items.len() + items.iter_mut().map(strip_null_values).sum::<usize>()
+        }
+        Value::Object(map) => {
+            let before = map.len();
+            map.retain(|_, v| !v.is_null());
+            before - map.len() + map.values_mut().map(strip_null_values).sum::<usize>()
+        }
+        _ => 0,
+    }
+}
+
+fn entries(kv: KvMap) -> Entries {
+    kv.into_iter().collect()
+}
+
+pub fn export() -> Bundle {
+    let mut repos: Vec<RepoBundle> = config::available_projects()
+        .into_par_iter()
+        .filter_map(|(name, path)| {
+            let keys = super::store_keys(name.as_str(), &path);
+            if keys.is_empty() {
+                return None;
+            }
+            let mut repo = RepoBundle {
+                github: crate::git::github_repo_from_remote(&path),
+                name: name.to_string(),
+                scope: entries(
+                    scope::snapshot(&ScopeTarget::Repo(name.to_string())).unwrap_or_default(),
+                ),
+                ..Default::default()
+            };
+            for key in keys {
+                let kv = entries(super::read_kv_file(&super::kv_file_for_key(&key, &path)));
+                match key.branch {
+                    Some(branch) if !kv.is_empty() => {
+                        repo.tasks.insert(branch.to_string(), kv);
+                    }
+                    Some(_) => {}
+                    None => repo.project = kv,
+                }
+            }
+            let empty = repo.scope.is_empty() && repo.project.is_empty() && repo.tasks.is_empty();
+            (!empty).then_some(repo)
+        })
+        .collect();
+    repos.sort_by(|a, b| a.name.cmp(&b.name));
+    Bundle {
+        version: VERSION,
+        global: entries(scope::snapshot(&ScopeTarget::Global).unwrap_or_default()),
+        repos,
+    }
+}
+
+/// Work out which of `incoming` to write over `local`, recording the outcome
+/// of each key in `report`.
+fn plan(
+    target: &str,
+    local: &KvMap,
+    incoming: &Entries,
+    overwrite: bool,
+    report: &mut ImportReport,
+) -> Vec<(String, Value)> {
+    let mut writes = vec![];
+    for (key, value) in incoming {
+        if let Err(error) = super::schema::validate(key, value) {
+            report.invalid.push(Invalid {
+                target: target.to_string(),
+                key: key.clone(),
+                error,
+            });
+            continue;
+        }
+        match local.get(key) {
+            Some(v) if v == value => report.unchanged += 1,
+            Some(v) if !overwrite => report.conflicts.push(Conflict {
+                target: target.to_string(),
+                key: key.clone(),
+                local: v.clone(),
+                incoming: value.clone(),
+            }),
+            _ => {
+                report.imported += 1;
+                writes.push((key.clone(), value.clone()));
+            }
+        }
+    }
+    writes
+}
+
+pub fn import(bundle: &Bundle, overwrite: bool, dry_run: bool) -> Result<ImportReport, String> {
+    if bundle.version != VERSION {
+        return Err(format!(
+            "Unsupported KV bundle version {} (expected {})",
+            bundle.version, VERSION
+        ));
+    }
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+    import_scope(
+        &ScopeTarget::Global,
+        "@global",
+        &bundle.global,
+        overwrite,
+        &mut report,
+    );
+
+    let available = config::available_projects();
+    let github_map = crate::task::build_github_repo_map();
+    for repo in &bundle.repos {
+        let local = repo
+            .github
+            .as_ref()
+            .and_then(|nwo| github_map.get(nwo))
+            .and_then(|name| available.get_key_value(name))
+            .or_else(|| available.iter().find(|(n, _)| n.as_str() == repo.name));
+        let Some((name, path)) = local else {
+            report
+                .unmatched
+                .push(repo.github.clone().unwrap_or_else(|| repo.name.clone()));
+            continue;
+        };
+        import_scope(
+            &ScopeTarget::Repo(name.to_string()),
+            &ScopeTarget::Repo(name.to_string()).to_string(),
+            &repo.scope,
+            overwrite,
+            &mut report,
+        );
+        let branches = std::iter::once((None, &repo.project))
+            .chain(repo.tasks.iter().map(|(b, kv)| (Some(b), kv)));
+        for (branch, kv) in branches {
+            if kv.is_empty() {
+                continue;
+            }
+            let mut project = Project {
+                repo_name: name.clone(),
+                repo_path: path.clone(),
+                branch: branch.map(BranchName::new),
+                kv: KvMap::new(),
+                cached: Default::default(),
+            };
+            import_project(&mut project, path, kv, overwrite, &mut report);
+        }
+    }
+    if !dry_run && report.imported > 0 {
+        projects::notify_state_change();
+    }
+    Ok(report)
+}
+
+fn import_scope(
+    target: &ScopeTarget,
+    label: &str,
+    incoming: &Entries,
+    overwrite: bool,
+    report: &mut ImportReport,
+) {
+    if incoming.is_empty() {
+        return;
+    }
+    let local = scope::snapshot(target).unwrap_or_default();
+    let imported = report.imported;
+    let writes = plan(label, &local, incoming, overwrite, report);
+    if report.dry_run {
+        return;
+    }
+    if let Err(error) = scope::set_all(target, "import", writes) {
+        report.failed(label, imported, error);
+    }
+}
+
+/// Import into an open project in place, or into the KV file of one that
+/// isn't open (`project` then carries just its identity).
+fn import_project(
+    project: &mut Project,
+    repo_path: &Path,
+    incoming: &Entries,
+    overwrite: bool,
+    report: &mut ImportReport,
+) {
+    let key = project.store_key();
+    let mut projects = projects::lock();
+    let project = match projects.get_mut(&key) {
+        Some(open) => open,
+        None => {
+            project.kv = super::read_kv_file(&super::kv_file_for_key(&key, repo_path));
+            project
+        }
+    };
+    let imported = report.imported;
+    let result = super::modify(project, |project| {
+        let writes = plan(&key.to_string(), &project.kv, incoming, overwrite, report);
+        if report.dry_run {
+            return;
+        }
+        for (kv_key, value) in writes {
+            super::update(project, "import", &kv_key, Some(value));
+        }
+    });
+    if let Err(error) = result {
+        report.failed(&key.to_string(), imported, error);
+    }
+}
+
+pub fn export_response() -> Response<Body> {
+    Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string_pretty(&export()).unwrap()))
+        .unwrap()
+}
+
+/// Import the JSON bundle in the request body.
+pub async fn import_request(req: Request<Body>, overwrite: bool, dry_run: bool) -> Response<Body> {
+    let bytes = hyper::body::to_bytes(req.into_body())
+        .await
+        .unwrap_or_default();
+    let result = serde_json::from_slice::<Bundle>(&bytes)
+        .map_err(|e| format!("Invalid KV bundle: {}", e))
+        .and_then(|bundle| import(&bundle, overwrite, dry_run));
+    match result {
+        Ok(report) => Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string_pretty(&report).unwrap()))
+            .unwrap(),
+        Err(e) => Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from(e))
+            .unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_plan_reports_conflicts_unless_overwriting() {
+        let local = KvMap::from([
+            ("land-in".to_string(), json!("editor")),
+            ("status".to_string(), json!("done")),
+        ]);
+        let incoming = Entries::from([
+            ("land-in".to_string(), json!("terminal")),
+            ("status".to_string(), json!("done")),
+            ("jira_key".to_string(), json!("ACT-1")),
+        ]);
+
+        let mut report = ImportReport::default();
+        let writes = plan("cli:feat", &local, &incoming, false, &mut report);
+        assert_eq!(writes, vec![("jira_key".to_string(), json!("ACT-1"))]);
+        assert_eq!((report.imported, report.unchanged), (1, 1));
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].key, "land-in");
+        assert_eq!(report.conflicts[0].local, json!("editor"));
+
+        let mut report = ImportReport::default();
+        let writes = plan("cli:feat", &local, &incoming, true, &mut report);
+        assert_eq!(writes.len(), 2);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_plan_skips_invalid_values() {
+        let incoming = Entries::from([
+            ("land-in".to_string(), json!("vscode")),
+            ("land_in".to_string(), json!("editor")),
+            ("notes".to_string(), json!("x")),
+        ]);
+        let mut report = ImportReport::default();
+        let writes = plan("cli", &KvMap::new(), &incoming, false, &mut report);
+        assert_eq!(writes, vec![("notes".to_string(), json!("x"))]);
+        let skipped: Vec<&str> = report.invalid.iter().map(|i| i.key.as_str()).collect();
+        assert_eq!(skipped, ["land-in", "land_in"]);
+        assert!(report.invalid[0].error.contains("one of terminal"));
+    }
+
+    #[test]
+    fn test_bundle_toml_round_trip() {
+        let bundle = Bundle {
+            version: 1,
+            global: Entries::from([("land-in".to_string(), json!("terminal"))]),
+            repos: vec![RepoBundle {
+                github: Some("acme/cli".to_string()),
+                name: "cli".to_string(),
+                tasks: BTreeMap::from([(
+                    "feat/x".to_string(),
+                    Entries::from([("aux-repos".to_string(), json!(["api"]))]),
+                )]),
+                ..Default::default()
+            }],
+        };
+        let text = toml::to_string(&bundle).unwrap();
+        let parsed: Bundle = toml::from_str(&text).unwrap();
+        assert_eq!(parsed.repos[0].tasks["feat/x"]["aux-repos"], json!(["api"]));
+        assert_eq!(parsed.global["land-in"], json!("terminal"));
+    }
+
+    #[test]
+    fn test_strip_nulls_for_toml() {
+        let mut bundle = Bundle {
+            version: VERSION,
+            global: Entries::from([
+                ("gone".to_string(), Value::Null),
+                (
+                    "build".to_string(),
+                    json!({"flags": null, "list": [1, null]}),
+                ),
+            ]),
+            ..Default::default()
+        };
+        assert!(toml::to_string(&bundle).is_err());
+        assert_eq!(bundle.strip_nulls(), 3);
+        assert_eq!(bundle.global["build"], json!({"list": [1]}));
+        assert!(toml::to_string(&bundle).is_ok());
+    }
+
+    #[test]
+    fn test_import_rejects_other_versions() {
+        let bundle = Bundle {
+            version: VERSION + 1,
+            ..Default::default()
+        };
+        assert!(import(&bundle, false, true)
+            .unwrap_err()
+            .contains("Unsupported KV bundle version"));
+    }
+
+    #[test]
+    fn test_import_project_reports_failed_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project {
+            repo_name: crate::config::CanonicalName::for_test("bundle-fail-test-repo"),
+            repo_path: dir.path().to_path_buf(),
+            branch: None,
+            kv: KvMap::new(),
+            cached: Default::default(),
+        };
+        // A directory where the KV file should be can't be replaced.
+        std::fs::create_dir_all(super::super::kv_file(&project).join("subdir")).unwrap();
+        let incoming = Entries::from([("notes".to_string(), json!("x"))]);
+        let mut report = ImportReport::default();
+        import_project(&mut project, dir.path(), &incoming, false, &mut report);
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].target, "bundle-fail-test-repo");
+    }
+
+    #[test]
+    fn test_imported_task_kv_survives_task_creation() {
+        let dir = tempfile::tempdir().unwrap();
+        let task = || Project {
+            repo_name: crate::config::CanonicalName::for_test("bundle-test-repo"),
+            repo_path: dir.path().to_path_buf(),
+            branch: Some(BranchName::new("feat/x")),
+            kv: KvMap::new(),
+            cached: Default::default(),
+        };
+        let incoming = Entries::from([("notes".to_string(), json!("from bundle"))]);
+        let mut report = ImportReport::default();
+        import_project(&mut task(), dir.path(), &incoming, false, &mut report);
+        assert_eq!(report.imported, 1);
+
+        // As `create_task` then `set_value_sync` do for a new task.
+        let mut created = task();
+        super::super::load_project_kv(&mut created);
+        super::super::modify(&mut created, |p| {
+            super::super::update(p, "task", "task_type", Some(json!("review")))
+        })
+        .unwrap();
+        let saved = super::super::read_kv_file(&super::super::kv_file(&created));
+        assert_eq!(saved["notes"], json!("from bundle"));
+        assert_eq!(saved["task_type"], json!("review"));
+    }
+}
@@ -0,0 +1,490 @@
+use hyper::{Body, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use super::scope::{self, ScopeTarget};
+use super::KvMap;
+use crate::project::{Project, ProjectKey};
+use crate::projects;
+
+/*
+    - Every KV mutation appends a `Change` to `{stem}.history.jsonl` next to the
+      KV file, for projects and scopes alike, once the change is saved. The
+      log is never rewritten.
+    - `source` says what made the change: `cli`, `http` (other API clients),
+      `refresh`, `task`, `pin`, `undo`, ...; `user` and `host` say who, since
+      KV files are shared by every checkout of the repo.
+    - Each change's `seq` follows the last line of the log, so appending
+      doesn't re-read the history.
+    - Undo appends the inverse of the most recent change that is neither an
+      undo nor already undone, so repeated undos walk back through history.
+      It refuses if the key has changed since (e.g. by an edit made outside
+      wormhole), rather than overwriting the newer value.
+*/
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub seq: u64,
+    /// Seconds since the Unix epoch
+    pub ts: u64,
+    pub source: String,
+    /// The user and host that made the change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    pub key: String,
+    /// None if the key was absent before the change
+    #[serde(default)]
+    pub old: Option<Value>,
+    /// None if the key was deleted
+    #[serde(default)]
+    pub new: Option<Value>,
+    /// The `seq` of the change this one reverts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undoes: Option<u64>,
+}
+
+impl Change {
+    pub fn render_terminal(&self) -> String {
+        let show = |v: &Option<Value>| match v {
+            Some(v) => v.to_string(),
+            None => "(unset)".to_string(),
+        };
+        let undo = match self.undoes {
+            Some(seq) => format!(" (undoes #{})", seq),
+            None => String::new(),
+        };
+        let by = match (&self.user, &self.host) {
+            (Some(user), Some(host)) => format!(" {}@{}", user, host),
+            (Some(user), None) => format!(" {}", user),
+            (None, Some(host)) => format!(" @{}", host),
+            (None, None) => String::new(),
+        };
+        format!(
+            "#{} {} {:<7}{} {}: {} -> {}{}",
+            self.seq,
+            format_ts(self.ts),
+            self.source,
+            by,
+            self.key,
+            show(&self.old),
+            show(&self.new),
+            undo
+        )
+    }
+}
+
+fn format_ts(ts: u64) -> String {
+    let (y, m, d) = crate::conversations::epoch_days_to_ymd(ts / 86400);
+    let secs = ts % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        y,
+        m,
+        d,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+fn history_file(kv_path: &Path) -> PathBuf {
+    kv_path.with_extension("history.jsonl")
+}
+
+pub fn delete_history_file(project: &Project) {
+    let _ = fs::remove_file(history_file(&super::kv_file(project)));
+}
+
+/// The history of the KV file at `kv_path`.
+pub(super) fn read_at(kv_path: &Path) -> Vec<Change> {
+    fs::read_to_string(history_file(kv_path))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Append a change to the project's history.
+pub fn record(
+    project: &Project,
+    source: &str,
+    key: &str,
+    old: Option<Value>,
+    new: Option<Value>,
+    undoes: Option<u64>,
+) {
+    record_at(&super::kv_file(project), source, key, old, new, undoes);
+}
+
+/// Record each key that differs between `before` and `after` in the history
+/// of the KV file at `kv_path`.
+pub(super) fn record_diff(kv_path: &Path, source: &str, before: &KvMap, after: &KvMap) {
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    for key in keys {
+        let (old, new) = (before.get(key), after.get(key));
+        if old != new {
+            record_at(kv_path, source, key, old.cloned(), new.cloned(), None);
+        }
+    }
+}
+
+/// The length of the history of the KV file at `kv_path`, for `truncate`.
+pub(super) fn mark(kv_path: &Path) -> u64 {
+    fs::metadata(history_file(kv_path)).map_or(0, |m| m.len())
+}
+
+/// Drop the changes appended to the history of the KV file at `kv_path` since
+/// `mark`, when they failed to save.
+pub(super) fn truncate(kv_path: &Path, mark: u64) {
+    let path = history_file(kv_path);
+    let result = fs::OpenOptions::new()
+        .write(true)
+        .open(&path)
+        .and_then(|f| f.set_len(mark));
+    if let Err(e) = result {
+        eprintln!(
+            "Failed to roll back KV history in {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+/// Append a change to the history of the KV file at `kv_path`.
+fn record_at(
+    kv_path: &Path,
+    source: &str,
+    key: &str,
+    old: Option<Value>,
+    new: Option<Value>,
+    undoes: Option<u64>,
+) {
+    let path = history_file(kv_path);
+    if let Err(e) = super::with_lock(kv_path, || append(&path, source, key, old, new, undoes)) {
+        eprintln!("Failed to record KV history: {}", e);
+    }
+}
+
+fn append(
+    path: &Path,
+    source: &str,
+    key: &str,
+    old: Option<Value>,
+    new: Option<Value>,
+    undoes: Option<u64>,
+) {
+    let (user, host) = identity();
+    let change = Change {
+        seq: last_seq(path).map_or(1, |seq| seq + 1),
+        ts: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        source: source.to_string(),
+        user: user.clone(),
+        host: host.clone(),
+        key: key.to_string(),
+        old,
+        new,
+        undoes,
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let result = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut f| writeln!(f, "{}", serde_json::to_string(&change).unwrap()));
+    if let Err(e) = result {
+        eprintln!("Failed to record KV history in {}: {}", path.display(), e);
+    }
+}
+
+/// The `seq` of the last change in the log at `path`, reading only as much of
+/// the end of the file as it takes to find the last line.
+fn last_seq(path: &Path) -> Option<u64> {
+    let mut file = fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let mut window: u64 = 4096;
+    loop {
+        let start = len.saturating_sub(window);
+        file.seek(SeekFrom::Start(start)).ok()?;
+        let mut tail = String::new();
+        Read::by_ref(&mut file)
+            .take(len - start)
+            .read_to_string(&mut tail)
+            .ok()?;
+        let body = tail.trim_end_matches('\n');
+        match body.rfind('\n') {
+            Some(i) => return parse_seq(&body[i + 1..]),
+            None if start == 0 => return parse_seq(body),
+            None => window *= 4,
+        }
+    }
+}
+
+fn parse_seq(line: &str) -> Option<u64> {
+    #[derive(Deserialize)]
+    struct Seq {
+        seq: u64,
+    }
+    serde_json::from_str::<Seq>(line).ok().map(|s| s.seq)
+}
+
+/// The user and host of this process, recorded with each change.
+fn identity() -> &'static (Option<String>, Option<String>) {
+    static IDENTITY: OnceLock<(Option<String>, Option<String>)> = OnceLock::new();
+    IDENTITY.get_or_init(|| {
+        let user = ["USER", "LOGNAME"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok())
+            .filter(|u| !u.is_empty());
+        let host = hostname();
+        (user, host)
+    })
+}
+
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    let ok = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) } == 0;
+    if !ok {
+        return None;
+    }
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    let name = String::from_utf8_lossy(&buf[..len]).to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// The most recent change (to `kv_key`, if given) that can still be undone.
+fn undoable(changes: &[Change], kv_key: Option<&str>) -> Option<Change> {
+    let undone: HashSet<u64> = changes.iter().filter_map(|c| c.undoes).collect();
+    changes
+        .iter()
+        .rev()
+        .filter(|c| c.undoes.is_none() && !undone.contains(&c.seq))
+        .find(|c| kv_key.is_none_or(|k| c.key == k))
+        .cloned()
+}
+
+pub fn get_history(key: &ProjectKey, kv_key: Option<&str>) -> Response<Body> {
+    let Some(project) = projects::lock().by_key(key) else {
+        return not_found(key);
+    };
+    history_response(&super::kv_file(&project), kv_key)
+}
+
+pub fn get_scope_history(target: &ScopeTarget, kv_key: Option<&str>) -> Response<Body> {
+    match scope::file(target) {
+        Some(path) => history_response(&path, kv_key),
+        None => scope_not_found(),
+    }
+}
+
+/// The changes (to `kv_key`, if given) in the history of the KV file at `kv_path`.
+fn history_response(kv_path: &Path, kv_key: Option<&str>) -> Response<Body> {
+    let changes: Vec<Change> = read_at(kv_path)
+        .into_iter()
+        .filter(|c| kv_key.is_none_or(|k| c.key == k))
+        .collect();
+    Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string_pretty(&changes).unwrap()))
+        .unwrap()
+}
+
+/// Revert the most recent undoable change (to `kv_key`, if given), returning
+/// the change recorded for the revert.
+pub fn undo(key: &ProjectKey, kv_key: Option<&str>) -> Response<Body> {
+    let mut projects = projects::lock();
+    let Some(project) = projects.get_mut(key) else {
+        return not_found(key);
+    };
+    let path = super::kv_file(project);
+    let result = super::modify(project, |project| {
+        let reverted = revert(&mut project.kv, &path, kv_key);
+        if let Ok(target) = &reverted {
+            record_undo(&path, target);
+        }
+        reverted
+    });
+    drop(projects);
+    undo_response(&path, result)
+}
+
+/// `undo` for a scope.
+pub fn undo_scope(target: &ScopeTarget, kv_key: Option<&str>) -> Response<Body> {
+    let Some(path) = scope::file(target) else {
+        return scope_not_found();
+    };
+    let result = scope::modify_recorded(
+        target,
+        |path, kv| revert(kv, path, kv_key),
+        |path, _, _, reverted| {
+            if let Ok(target) = reverted {
+                record_undo(path, target);
+            }
+        },
+    );
+    undo_response(&path, result)
+}
+
+fn undo_response(
+    kv_path: &Path,
+    result: Result<Result<Change, (StatusCode, String)>, String>,
+) -> Response<Body> {
+    match result {
+        Ok(Ok(_)) => {
+            crate::projects::notify_state_change();
+            let change = read_at(kv_path).pop();
+            Response::builder()
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string_pretty(&change).unwrap()))
+                .unwrap()
+        }
+        Ok(Err((status, e))) => Response::builder()
+            .status(status)
+            .body(Body::from(e))
+            .unwrap(),
+        Err(e) => Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(e))
+            .unwrap(),
+    }
+}
+
+/// Revert, in `kv`, the most recent undoable change in the history of the KV
+/// file at `kv_path`, returning that change. Does not record the revert.
+fn revert(
+    kv: &mut KvMap,
+    kv_path: &Path,
+    kv_key: Option<&str>,
+) -> Result<Change, (StatusCode, String)> {
+    let Some(target) = undoable(&read_at(kv_path), kv_key) else {
+        return Err((StatusCode::NOT_FOUND, "Nothing to undo".to_string()));
+    };
+    let current = kv.get(&target.key);
+    if current != target.new.as_ref() {
+        let show = |v: Option<&Value>| v.map_or("(unset)".to_string(), |v| v.to_string());
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "Not undoing #{}: {} is now {}, not {} as that change left it",
+                target.seq,
+                target.key,
+                show(current),
+                show(target.new.as_ref())
+            ),
+        ));
+    }
+    match &target.old {
+        Some(v) => kv.insert(target.key.clone(), v.clone()),
+        None => kv.remove(&target.key),
+    };
+    Ok(target)
+}
+
+/// Record the revert of `target` in the history of the KV file at `kv_path`.
+fn record_undo(kv_path: &Path, target: &Change) {
+    record_at(
+        kv_path,
+        "undo",
+        &target.key,
+        target.new.clone(),
+        target.old.clone(),
+        Some(target.seq),
+    );
+}
+
+fn scope_not_found() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::from("Scope not found"))
+        .unwrap()
+}
+
+fn not_found(key: &ProjectKey) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::from(format!("Project '{}' not found", key)))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn change(seq: u64, key: &str, undoes: Option<u64>) -> Change {
+        Change {
+            seq,
+            ts: 0,
+            source: "cli".to_string(),
+            user: None,
+            host: None,
+            key: key.to_string(),
+            old: None,
+            new: Some(json!("v")),
+            undoes,
+        }
+    }
+
+    #[test]
+    fn test_undo_walks_back_through_history() {
+        let mut changes = vec![change(1, "a", None), change(2, "b", None)];
+        assert_eq!(undoable(&changes, None).unwrap().seq, 2);
+        changes.push(change(3, "b", Some(2)));
+        assert_eq!(undoable(&changes, None).unwrap().seq, 1);
+        changes.push(change(4, "a", Some(1)));
+        assert!(undoable(&changes, None).is_none());
+    }
+
+    #[test]
+    fn test_undo_filters_by_key() {
+        let changes = vec![change(1, "a", None), change(2, "b", None)];
+        assert_eq!(undoable(&changes, Some("a")).unwrap().seq, 1);
+        assert!(undoable(&changes, Some("c")).is_none());
+    }
+
+    #[test]
+    fn test_last_seq_reads_final_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repo.history.jsonl");
+        assert_eq!(last_seq(&path), None);
+        let mut big = change(1, "a", None);
+        big.new = Some(json!("x".repeat(10_000)));
+        let lines: Vec<String> = [big, change(2, "b", None), change(41, "c", None)]
+            .iter()
+            .map(|c| serde_json::to_string(c).unwrap())
+            .collect();
+        fs::write(&path, format!("{}\n", lines[..1].join("\n"))).unwrap();
+        assert_eq!(last_seq(&path), Some(1));
+        fs::write(&path, format!("{}\n", lines.join("\n"))).unwrap();
+        assert_eq!(last_seq(&path), Some(41));
+    }
+
+    #[test]
+    fn test_render_change() {
+        let mut c = change(7, "land-in", None);
+        c.ts = 1_760_778_000;
+        c.old = Some(json!("editor"));
+        assert_eq!(
+            c.render_terminal(),
+            r#"#7 2025-10-18T09:00:00Z cli     land-in: "editor" -> "v""#
+        );
+        c.user = Some("dan".into());
+        c.host = Some("laptop".into());
+        assert_eq!(
+            c.render_terminal(),
+            r#"#7 2025-10-18T09:00:00Z cli     dan@laptop land-in: "editor" -> "v""#
+        );
+    }
+}
@@ -0,0 +1,938 @@
+use hyper::{Body, Response, StatusCode};
+use rayon::prelude::*;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::git;
+use crate::project::{Project, ProjectKey};
+use crate::projects;
+
+pub mod bundle;
+pub mod history;
+pub mod schema;
+pub mod scope;
+pub mod watch;
+
+/// Project KV data. Values are arbitrary JSON; files written before values
+/// were typed hold only strings, which load unchanged as JSON strings.
+pub type KvMap = HashMap<String, Value>;
+
+/// A KV value as text: strings verbatim, anything else as compact JSON.
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+/// Set (`Some`) or remove (`None`) `kv_key`, recording the change in the
+/// project's history. Returns true if the value changed. Does not save.
+pub fn update(project: &mut Project, source: &str, kv_key: &str, new: Option<Value>) -> bool {
+    let old = match &new {
+        Some(v) => project.kv.insert(kv_key.to_string(), v.clone()),
+        None => project.kv.remove(kv_key),
+    };
+    if old == new {
+        return false;
+    }
+    history::record(project, source, kv_key, old, new, None);
+    true
+}
+
+/// Return `kv_key`, or the value at JSON pointer `pointer` within it. String
+/// values are returned as plain text unless `json` is set; other values are
+/// always returned as JSON.
+pub fn get_value(
+    key: &ProjectKey,
+    kv_key: &str,
+    pointer: Option<&str>,
+    json: bool,
+) -> Response<Body> {
+    if let Some(response) = invalid_pointer(pointer) {
+        return response;
+    }
+    let projects = projects::lock();
+    let Some(project) = projects.by_key(key) else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from(format!("Project '{}' not found", key)))
+            .unwrap();
+    };
+    let value = project
+        .kv
+        .get(kv_key)
+        .and_then(|v| v.pointer(pointer.unwrap_or("")));
+    value_response(value, json, || {
+        format!(
+            "Key '{}{}' not found in project '{}'",
+            kv_key,
+            pointer.unwrap_or(""),
+            key
+        )
+    })
+}
+
+/// A 400 response if `pointer` is not a valid JSON pointer, which would
+/// otherwise look like a missing value.
+fn invalid_pointer(pointer: Option<&str>) -> Option<Response<Body>> {
+    let error = pointer_tokens(pointer?).err()?;
+    Some(
+        Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from(error))
+            .unwrap(),
+    )
+}
+
+/// Respond with a KV value: plain text for strings unless `json` is set, JSON
+/// otherwise, or 404 with `not_found()` if there is no value.
+fn value_response(
+    value: Option<&Value>,
+    json: bool,
+    not_found: impl FnOnce() -> String,
+) -> Response<Body> {
+    match value {
+        Some(Value::String(s)) if !json => Response::new(Body::from(s.clone())),
+        Some(value) => Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(value.to_string()))
+            .unwrap(),
+        None => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from(not_found()))
+            .unwrap(),
+    }
+}
+
+/// Parse a request body as a KV value: any JSON value if `json` is set, else a
+/// string.
+fn body_value(bytes: &[u8], json: bool) -> Result<Value, String> {
+    if json {
+        serde_json::from_slice(bytes).map_err(|e| format!("Invalid JSON value: {}", e))
+    } else {
+        Ok(Value::String(String::from_utf8_lossy(bytes).to_string()))
+    }
+}
+
+/// Set `kv_key` (or the value at JSON pointer `pointer` within it) from the
+/// request body: a string, or any JSON value if `json` is set.
+pub async fn set_value(
+    key: &ProjectKey,
+    source: &str,
+    kv_key: &str,
+    pointer: Option<&str>,
+    json: bool,
+    body: Body,
+) -> Response<Body> {
+    let bytes = hyper::body::to_bytes(body).await.unwrap_or_default();
+    let value = match body_value(&bytes, json) {
+        Ok(v) => v,
+        Err(e) => {
+            return Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(e))
+                .unwrap()
+        }
+    };
+
+    let mut projects = projects::lock();
+
+    if let Some(project) = projects.get_mut(key) {
+        let result = modify(project, |project| {
+            let mut root = project.kv.get(kv_key).cloned().unwrap_or(Value::Null);
+            set_pointer(&mut root, pointer.unwrap_or(""), value)
+                .and_then(|()| schema::validate(kv_key, &root))?;
+            update(project, source, kv_key, Some(root));
+            Ok::<_, String>(())
+        });
+        let error = match result {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some((StatusCode::BAD_REQUEST, e)),
+            Err(e) => Some((StatusCode::INTERNAL_SERVER_ERROR, e)),
+        };
+        if let Some((status, e)) = error {
+            return Response::builder()
+                .status(status)
+                .body(Body::from(e))
+                .unwrap();
+        }
+        drop(projects);
+        crate::projects::notify_state_change();
+        Response::new(Body::empty())
+    } else {
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from(format!("Project '{}' not found", key)))
+            .unwrap()
+    }
+}
+
+pub fn set_value_sync(key: &ProjectKey, source: &str, kv_key: &str, value: &str) {
+    let mut projects = projects::lock();
+
+    if let Some(project) = projects.get_mut(key) {
+        let result = modify(project, |project| {
+            update(project, source, kv_key, Some(value.into()))
+        });
+        if let Err(e) = result {
+            eprintln!("Failed to set {} for {}: {}", kv_key, key, e);
+        }
+        drop(projects);
+        crate::projects::notify_state_change();
+    }
+}
+
+fn pointer_tokens(pointer: &str) -> Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+    let rest = pointer
+        .strip_prefix('/')
+        .ok_or_else(|| format!("JSON pointer '{}' must start with '/'", pointer))?;
+    Ok(rest
+        .split('/')
+        .map(|t| t.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn array_index(token: &str, len: usize) -> Result<usize, String> {
+    token
+        .parse::<usize>()
+        .ok()
+        .filter(|i| *i < len)
+        .ok_or_else(|| format!("array index '{}' out of range (length {})", token, len))
+}
+
+/// Set the value at JSON pointer `pointer` within `root`, creating missing
+/// (or null) containers on the way: an array where the next token is `-`,
+/// otherwise an object. A `-` token appends to an array.
+pub fn set_pointer(root: &mut Value, pointer: &str, value: Value) -> Result<(), String> {
+    let tokens = pointer_tokens(pointer)?;
+    let Some((last, parents)) = tokens.split_last() else {
+        *root = value;
+        return Ok(());
+    };
+    let mut target = root;
+    for token in parents {
+        target = child_mut(target, token)?;
+    }
+    *child_mut(target, last)? = value;
+    Ok(())
+}
+
+/// The child of `target` at `token`, creating `target` as a container if it
+/// is null and appending an element for `-`.
+fn child_mut<'a>(target: &'a mut Value, token: &str) -> Result<&'a mut Value, String> {
+    if target.is_null() {
+        *target = if token == "-" {
+            Value::Array(Vec::new())
+        } else {
+            Value::Object(Default::default())
+        };
+    }
+    match target {
+        Value::Object(map) => Ok(map.entry(token.to_string()).or_insert(Value::Null)),
+        Value::Array(items) => {
+            let i = if token == "-" {
+                items.push(Value::Null);
+                items.len() - 1
+            } else {
+                array_index(token, items.len())?
+            };
+            Ok(&mut items[i])
+        }
+        other => Err(format!(
+            "cannot set '{}': its parent is {}, not an object or array",
+            token,
+            json_type(other)
+        )),
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Remove the value at JSON pointer `pointer` within `root`. Returns false if
+/// there was nothing there.
+pub fn remove_pointer(root: &mut Value, pointer: &str) -> bool {
+    let Ok(tokens) = pointer_tokens(pointer) else {
+        return false;
+    };
+    let Some((last, _)) = tokens.split_last() else {
+        return false;
+    };
+    let parent = &pointer[..pointer.rfind('/').unwrap_or(0)];
+    match root.pointer_mut(parent) {
+        Some(Value::Object(map)) => map.remove(last).is_some(),
+        Some(Value::Array(items)) => match array_index(last, items.len()) {
+            Ok(i) => {
+                items.remove(i);
+                true
+            }
+            Err(_) => false,
+        },
+        _ => false,
+    }
+}
+
+/// Delete `kv_key` from every project that has it. Returns the cleared project
+/// keys as JSON. Used to reset transient UI state (e.g. `land-in` pins) across
+/// all projects in one shot.
+pub fn delete_value_all(source: &str, kv_key: &str) -> Response<Body> {
+    let mut projects = projects::lock();
+    let mut cleared: Vec<String> = Vec::new();
+    for key in projects.keys() {
+        if let Some(project) = projects.get_mut(&key) {
+            match modify(project, |project| update(project, source, kv_key, None)) {
+                Ok(true) => cleared.push(key.to_string()),
+                Ok(false) => {}
+                Err(e) => eprintln!("Failed to delete {} for {}: {}", kv_key, key, e),
+            }
+        }
+    }
+    drop(projects);
+    if !cleared.is_empty() {
+        crate::projects::notify_state_change();
+    }
+    let body = serde_json::json!({ "key": kv_key, "cleared": cleared });
+    Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Delete `kv_key`, or only the value at JSON pointer `pointer` within it.
+pub fn delete_value(
+    key: &ProjectKey,
+    source: &str,
+    kv_key: &str,
+    pointer: Option<&str>,
+) -> Response<Body> {
+    if let Some(response) = invalid_pointer(pointer) {
+        return response;
+    }
+    let mut projects = projects::lock();
+
+    if let Some(project) = projects.get_mut(key) {
+        let removed = modify(project, |project| match (pointer, project.kv.get(kv_key)) {
+            (Some(p), Some(v)) => {
+                let mut root = v.clone();
+                remove_pointer(&mut root, p) && update(project, source, kv_key, Some(root))
+            }
+            (Some(_), None) => false,
+            (None, _) => update(project, source, kv_key, None),
+        });
+        if let Err(e) = removed {
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from(e))
+                .unwrap()
+        } else if removed == Ok(true) {
+            drop(projects);
+            crate::projects::notify_state_change();
+            Response::new(Body::empty())
+        } else {
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from(format!(
+                    "Key '{}' not found in project '{}'",
+                    kv_key, key
+                )))
+                .unwrap()
+        }
+    } else {
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from(format!("Project '{}' not found", key)))
+            .unwrap()
+    }
+}
+
+pub fn get_project_kv(key: &ProjectKey) -> Response<Body> {
+    let projects = projects::lock();
+    match projects.by_key(key) {
+        Some(project) => {
+            let json = serde_json::to_string_pretty(&project.kv).unwrap();
+            Response::new(Body::from(json))
+        }
+        None => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from(format!("Project '{}' not found", key)))
+            .unwrap(),
+    }
+}
+
+fn wormhole_dir(project: &Project) -> PathBuf {
+    crate::git::git_common_dir(&project.repo_path).join("wormhole")
+}
+
+fn kv_file(project: &Project) -> PathBuf {
+    wormhole_dir(project)
+        .join("kv")
+        .join(kv_file_name(&project.store_key()))
+}
+
+/// `{repo}.json` or `{repo}_{encoded branch}.json`
+fn kv_file_name(key: &ProjectKey) -> String {
+    let stem = match &key.branch {
+        Some(branch) => format!(
+            "{}_{}",
+            key.repo,
+            git::encode_branch_for_path(branch.as_str())
+        ),
+        None => key.repo.to_string(),
+    };
+    format!("{}.json", stem)
+}
+
+/// Load the project's KV from its file, e.g. for a task being created whose
+/// KV was imported before it existed.
+pub fn load_project_kv(project: &mut Project) {
+    project.kv = read_kv_file(&kv_file(project));
+}
+
+pub fn delete_kv_file(project: &Project) {
+    let path = kv_file(project);
+    let _ = std::fs::remove_file(path);
+    history::delete_history_file(project);
+}
+
+/// Change the project's KV with `f` as one read-modify-write of its file,
+/// saving if anything changed. Under the KV lock, first pick up changes made
+/// to the file by other writers since it was read (recorded as `external`, as
+/// the watcher would), so that `f` never works from a stale copy and the save
+/// never drops someone else's update. Fails, without calling `f`, if the file
+/// can't be locked, and fails if the save does, leaving the project's KV and
+/// history as they were before `f`.
+pub fn modify<T>(project: &mut Project, f: impl FnOnce(&mut Project) -> T) -> Result<T, String> {
+    let path = kv_file(project);
+    with_lock(&path, || {
+        if let Some(kv) = try_read_kv_file(&path) {
+            watch::apply_external(project, kv);
+        }
+        let before = project.kv.clone();
+        let history_mark = history::mark(&path);
+        let result = f(project);
+        if project.kv != before {
+            if let Err(e) = save_project_kv(&path, project) {
+                project.kv = before;
+                history::truncate(&path, history_mark);
+                return Err(e);
+            }
+        }
+        Ok(result)
+    })?
+}
+
+/// Write the project's KV to `path`. Call with the KV lock held.
+fn save_project_kv(path: &Path, project: &Project) -> Result<(), String> {
+    // Never overwrite (or delete) a file we couldn't load: keep it for `doctor`.
+    backup_if_corrupt(path);
+
+    let result = if project.kv.is_empty() {
+        remove_durably(path)
+    } else {
+        let json = serde_json::to_string_pretty(&project.kv).unwrap();
+        write_atomic(path, &json)
+    };
+    result.map_err(|e| format!("Failed to save KV data for {}: {}", project.repo_name, e))
+}
+
+thread_local! {
+    /// Lock files this thread holds, so that nested `with_lock` calls (e.g.
+    /// recording history during `modify`) don't deadlock on a second flock.
+    static HELD: std::cell::RefCell<Vec<PathBuf>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// How long `with_lock` waits for another process to release a KV lock. The
+/// server calls it holding the projects lock, so it must not wait long.
+const LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+const LOCK_RETRY: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Run `f` holding an exclusive advisory lock on the lock file beside the KV
+/// file at `kv_path`, serializing writes from the server, CLI and scripts.
+/// Fails, without calling `f`, if the lock can't be taken within
+/// `LOCK_TIMEOUT`.
+fn with_lock<T>(kv_path: &Path, f: impl FnOnce() -> T) -> Result<T, String> {
+    use std::os::fd::AsRawFd;
+
+    let path = kv_path.with_extension("lock");
+    if HELD.with(|held| held.borrow().contains(&path)) {
+        return Ok(f());
+    }
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let lock_error = |e: std::io::Error| format!("Failed to lock {}: {}", path.display(), e);
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(lock_error)?;
+    let deadline = std::time::Instant::now() + LOCK_TIMEOUT;
+    while unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let e = std::io::Error::last_os_error();
+        if e.kind() != std::io::ErrorKind::WouldBlock {
+            return Err(lock_error(e));
+        }
+        if std::time::Instant::now() >= deadline {
+            return Err(format!(
+                "Timed out after {:?} waiting for the lock on {}",
+                LOCK_TIMEOUT,
+                path.display()
+            ));
+        }
+        std::thread::sleep(LOCK_RETRY);
+    }
+    // Dropped before `file`, whose closing releases the lock, even if `f` panics.
+    let _held = Held::new(path.clone());
+    Ok(f())
+}
+
+/// Marks a lock file as held by this thread for as long as it lives.
+struct Held(PathBuf);
+
+impl Held {
+    fn new(path: PathBuf) -> Self {
+        HELD.with(|held| held.borrow_mut().push(path.clone()));
+        Held(path)
+    }
+}
+
+impl Drop for Held {
+    fn drop(&mut self) {
+        HELD.with(|held| held.borrow_mut().retain(|p| *p != self.0));
+    }
+}
+
+/// Write `contents` to a temporary file beside `path` and rename it into
+/// place, so that readers never see a partially written file.
+fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.tmp.{}", name, std::process::id()));
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    sync_parent(path)
+}
+
+/// Remove `path` if it exists, making the removal durable.
+fn remove_durably(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Ok(()) => sync_parent(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// fsync the directory containing `path`, so that a rename or removal in it
+/// survives a crash.
+fn sync_parent(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) => std::fs::File::open(parent)?.sync_all(),
+        None => Ok(()),
+    }
+}
+
+/// Path a corrupt KV file is moved to before it would be overwritten.
+fn corrupt_backup_path(path: &Path) -> PathBuf {
+    path.with_extension("json.corrupt")
+}
+
+/// Why `path` can't be loaded as KV data, or None if it is valid or missing.
+fn corruption(path: &Path) -> Option<String> {
+    let data = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str::<KvMap>(&data)
+        .err()
+        .map(|e| e.to_string())
+}
+
+fn backup_if_corrupt(path: &Path) {
+    if let Some(e) = corruption(path) {
+        let backup = corrupt_backup_path(path);
+        eprintln!(
+            "Corrupt KV file {} ({}); moved to {}",
+            path.display(),
+            e,
+            backup.display()
+        );
+        let _ = std::fs::rename(path, backup);
+    }
+}
+
+pub fn load_kv_data(projects: &mut projects::Projects) {
+    use std::fs;
+
+    for project in projects.all_mut() {
+        let path = kv_file(project);
+        if !path.exists() {
+            continue;
+        }
+
+        let data = match fs::read_to_string(&path) {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+
+        match serde_json::from_str::<KvMap>(&data) {
+            Ok(kv) => project.kv = kv,
+            Err(e) => eprintln!(
+                "Corrupt KV file {}: {} (see `wormhole doctor persisted-data`)",
+                path.display(),
+                e
+            ),
+        }
+    }
+    scope::load(projects);
+}
+
+/// List all KV data from disk for all discoverable projects and tasks.
+/// Does not use cached data - reads directly from disk with concurrent I/O.
+pub fn list_all_kv_fresh() -> Response<Body> {
+    let available = config::available_projects();
+
+    // Collect all (store_key, repo_path) pairs for projects and tasks
+    let entries: Vec<(ProjectKey, PathBuf)> = available
+        .into_par_iter()
+        .flat_map(|(name, path)| {
+            store_keys(name.as_str(), &path)
+                .into_iter()
+                .map(|key| (key, path.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // Read KV files concurrently
+    let all_kv: HashMap<String, KvMap> = entries
+        .into_par_iter()
+        .filter_map(|(store_key, repo_path)| {
+            let kv_path = kv_file_for_key(&store_key, &repo_path);
+            let data = std::fs::read_to_string(&kv_path).ok()?;
+            let kv: KvMap = serde_json::from_str(&data).ok()?;
+            if kv.is_empty() {
+                None
+            } else {
+                Some((store_key.to_string(), kv))
+            }
+        })
+        .collect();
+
+    let json = serde_json::to_string_pretty(&all_kv).unwrap();
+    Response::new(Body::from(json))
+}
+
+/// Keys of all discoverable projects and tasks whose KV on disk matches
+/// `selector` (see `Selector::matches_stored`) and lacks every key in
+/// `missing`, as a sorted JSON array.
+pub fn find(selector: Option<&str>, missing: &[&str]) -> Response<Body> {
+    let selector = match selector.map(crate::selector::Selector::parse).transpose() {
+        Ok(s) => s,
+        Err(e) => {
+            return Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(format!("Invalid --where expression: {}", e)))
+                .unwrap()
+        }
+    };
+    if let Some(Err(e)) = selector.as_ref().map(|s| s.check_stored_fields()) {
+        return Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from(e))
+            .unwrap();
+    }
+    let mut keys: Vec<String> = config::available_projects()
+        .into_par_iter()
+        .flat_map(|(name, path)| {
+            store_keys(name.as_str(), &path)
+                .into_iter()
+                .filter(|key| {
+                    let project = Project {
+                        repo_name: name.clone(),
+                        repo_path: path.clone(),
+                        branch: key.branch.clone(),
+                        kv: read_kv_file(&kv_file_for_key(key, &path)),
+                        cached: Default::default(),
+                    };
+                    find_matches(&project, selector.as_ref(), missing)
+                })
+                .map(|key| key.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    keys.sort();
+    Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string_pretty(&keys).unwrap()))
+        .unwrap()
+}
+
+/// Whether `project` sets none of `missing` and matches `selector`, which is
+/// evaluated, as `--where` is everywhere else, on the project's resolved KV.
+fn find_matches(
+    project: &Project,
+    selector: Option<&crate::selector::Selector>,
+    missing: &[&str],
+) -> bool {
+    missing.iter().all(|k| !project.kv.contains_key(*k))
+        && selector
+            .is_none_or(|s| s.matches_stored(&project.store_key(), &scope::resolved_kv(project)))
+}
+
+/// The store keys of a repo's main project and of its tasks (worktrees under
+/// the wormhole worktree dir), or none if `path` is not a git repo.
+fn store_keys(name: &str, path: &Path) -> Vec<ProjectKey> {
+    if !git::is_git_repo(path) {
+        return vec![];
+    }
+    let mut keys = vec![ProjectKey::project(name)];
+    let worktrees_base = config::worktree_dir().join(name);
+    for wt in git::list_worktrees(path) {
+        if wt.path.starts_with(&worktrees_base) {
+            if let Some(branch) = wt.branch {
+                keys.push(ProjectKey::task(name, branch));
+            }
+        }
+    }
+    keys
+}
+
+/// Read a KV file, treating a missing or unreadable file as empty.
+fn read_kv_file(path: &Path) -> KvMap {
+    try_read_kv_file(path).unwrap_or_default()
+}
+
+/// Read a KV file; None if it is missing, unreadable or corrupt.
+fn try_read_kv_file(path: &Path) -> Option<KvMap> {
+    let data = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn kv_file_for_key(key: &ProjectKey, repo_path: &Path) -> PathBuf {
+    git::git_common_dir(repo_path)
+        .join("wormhole")
+        .join("kv")
+        .join(kv_file_name(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_string_files_load_as_json_strings() {
+        let kv: KvMap = serde_json::from_str(r#"{"land-in": "editor"}"#).unwrap();
+        assert_eq!(kv["land-in"], "editor");
+        assert_eq!(value_to_string(&kv["land-in"]), "editor");
+        assert_eq!(value_to_string(&json!(["a", 1])), r#"["a",1]"#);
+    }
+
+    #[test]
+    fn test_set_pointer_creates_and_replaces() {
+        let mut v = Value::Null;
+        set_pointer(&mut v, "/build/flags", json!(["-v"])).unwrap();
+        set_pointer(&mut v, "/build/flags/-", json!("-q")).unwrap();
+        set_pointer(&mut v, "/build/flags/0", json!("-x")).unwrap();
+        set_pointer(&mut v, "/a~1b", json!(true)).unwrap();
+        assert_eq!(v, json!({"build": {"flags": ["-x", "-q"]}, "a/b": true}));
+        assert!(set_pointer(&mut v, "/build/flags/5", json!(1)).is_err());
+        assert!(set_pointer(&mut v, "/a~1b/c", json!(1)).is_err());
+        assert!(set_pointer(&mut v, "build", json!(1)).is_err());
+        set_pointer(&mut v, "", json!(3)).unwrap();
+        assert_eq!(v, json!(3));
+    }
+
+    #[test]
+    fn test_set_pointer_append_creates_array() {
+        let mut v = Value::Null;
+        set_pointer(&mut v, "/-", json!("web")).unwrap();
+        assert_eq!(v, json!(["web"]));
+        set_pointer(&mut v, "/-", json!("api")).unwrap();
+        assert_eq!(v, json!(["web", "api"]));
+
+        let mut v = json!({"repos": null});
+        set_pointer(&mut v, "/repos/-", json!("a")).unwrap();
+        set_pointer(&mut v, "/tags/-/name", json!("x")).unwrap();
+        assert_eq!(v, json!({"repos": ["a"], "tags": [{"name": "x"}]}));
+    }
+
+    #[test]
+    fn test_set_pointer_non_container_parent_errors() {
+        let mut v = json!({"name": "web", "n": 1});
+        assert_eq!(
+            set_pointer(&mut v, "/name/-", json!("x")).unwrap_err(),
+            "cannot set '-': its parent is a string, not an object or array"
+        );
+        assert!(set_pointer(&mut v, "/n/a/b", json!(1))
+            .unwrap_err()
+            .contains("a number"));
+        assert_eq!(v, json!({"name": "web", "n": 1}));
+    }
+
+    #[test]
+    fn test_write_atomic_and_corrupt_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kv").join("repo_branch.json");
+        write_atomic(&path, r#"{"a": "1"}"#).unwrap();
+        assert_eq!(corruption(&path), None);
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+
+        std::fs::write(&path, r#"{"a": "#).unwrap();
+        assert!(corruption(&path).is_some());
+        backup_if_corrupt(&path);
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::read_to_string(corrupt_backup_path(&path)).unwrap(),
+            r#"{"a": "#
+        );
+    }
+
+    #[test]
+    fn test_modify_keeps_concurrent_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project {
+            repo_name: crate::config::CanonicalName::for_test("repo"),
+            repo_path: dir.path().to_path_buf(),
+            branch: None,
+            kv: KvMap::new(),
+            cached: Default::default(),
+        };
+        assert!(modify(&mut project, |p| update(p, "cli", "a", Some(json!("1")))).unwrap());
+        // Another writer adds a key after we loaded the file.
+        let path = kv_file(&project);
+        write_atomic(&path, r#"{"a": "1", "b": "2"}"#).unwrap();
+
+        modify(&mut project, |p| update(p, "cli", "c", Some(json!("3")))).unwrap();
+        assert_eq!(
+            read_kv_file(&path),
+            KvMap::from([
+                ("a".to_string(), json!("1")),
+                ("b".to_string(), json!("2")),
+                ("c".to_string(), json!("3")),
+            ])
+        );
+        let sources: Vec<String> = history::read_at(&kv_file(&project))
+            .into_iter()
+            .map(|c| c.source)
+            .collect();
+        assert_eq!(sources, ["cli", "external", "cli"]);
+    }
+
+    #[test]
+    fn test_modify_fails_and_restores_when_save_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project {
+            repo_name: crate::config::CanonicalName::for_test("repo"),
+            repo_path: dir.path().to_path_buf(),
+            branch: None,
+            kv: KvMap::new(),
+            cached: Default::default(),
+        };
+        assert!(modify(&mut project, |p| update(p, "cli", "a", Some(json!("1")))).unwrap());
+        // A directory where the KV file should be can't be replaced.
+        let path = kv_file(&project);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::create_dir_all(path.join("subdir")).unwrap();
+
+        assert!(modify(&mut project, |p| update(p, "cli", "b", Some(json!("2")))).is_err());
+        assert_eq!(project.kv, KvMap::from([("a".to_string(), json!("1"))]));
+        let keys: Vec<String> = history::read_at(&kv_file(&project))
+            .into_iter()
+            .map(|c| c.key)
+            .collect();
+        assert_eq!(keys, ["a"]);
+    }
+
+    #[test]
+    fn test_with_lock_fails_without_lock_and_releases_after_panic() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("file"), "").unwrap();
+        let unlockable = dir.path().join("file/kv.json");
+        assert!(with_lock(&unlockable, || panic!("ran without the lock")).is_err());
+
+        let path = dir.path().join("kv.json");
+        let lock = path.with_extension("lock");
+        let panicked = std::panic::catch_unwind(|| with_lock(&path, || panic!("in f")));
+        assert!(panicked.is_err());
+        assert!(!HELD.with(|held| held.borrow().contains(&lock)));
+
+        // Another holder (here another open file description) times out the wait.
+        use std::os::fd::AsRawFd;
+        let other = std::fs::File::open(&lock).unwrap();
+        assert_eq!(unsafe { libc::flock(other.as_raw_fd(), libc::LOCK_EX) }, 0);
+        let error = with_lock(&path, || panic!("ran without the lock")).unwrap_err();
+        assert!(error.contains("Timed out"));
+        drop(other);
+        assert!(with_lock(&path, || ()).is_ok());
+    }
+
+    #[test]
+    fn test_get_value_rejects_invalid_pointer() {
+        let key = ProjectKey::parse("pointer-test-missing-project");
+        let response = get_value(&key, "a", Some("b/c"), false);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(hyper::body::to_bytes(response.into_body()));
+        assert_eq!(
+            &body.unwrap()[..],
+            b"JSON pointer 'b/c' must start with '/'"
+        );
+        let response = get_value(&key, "a", Some("/b"), false);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_find_matches_inherited_kv() {
+        scope::set_repo_for_test(
+            "find-scope-test-repo",
+            KvMap::from([("team".to_string(), json!("infra"))]),
+        );
+        let project = Project {
+            repo_name: crate::config::CanonicalName::for_test("find-scope-test-repo"),
+            repo_path: "/nonexistent".into(),
+            branch: Some(crate::project::BranchName::new("feat")),
+            kv: KvMap::from([("status".to_string(), json!("done"))]),
+            cached: Default::default(),
+        };
+        let matches = |expr: &str| {
+            let selector = crate::selector::Selector::parse(expr).unwrap();
+            find_matches(&project, Some(&selector), &[])
+        };
+        assert!(matches("kv.team=infra"));
+        assert!(matches("kv.team=infra and kv.status=done"));
+        assert!(!matches("kv.team=web"));
+        // --missing looks only at the project's own KV
+        assert!(find_matches(&project, None, &["team"]));
+        assert!(!find_matches(&project, None, &["status"]));
+    }
+
+    #[test]
+    fn test_remove_pointer() {
+        let mut v = json!({"repos": ["a", "b"], "x": 1});
+        assert!(remove_pointer(&mut v, "/repos/0"));
+        assert!(remove_pointer(&mut v, "/x"));
+        assert!(!remove_pointer(&mut v, "/missing"));
+        assert_eq!(v, json!({"repos": ["b"]}));
+    }
+}
@@ -0,0 +1,241 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/*
+    - Keys wormhole itself reads or writes. Setting one of them via `PUT /kv`
+      (whole values and pointer writes) or a bundle import checks the value's
+      type; setting an unknown key that looks like a typo of one (`land_in`,
+      `langd-in`) is rejected. Other keys are free-form.
+    - Only case and `_`/`-` differences count as typos of short keys, so that
+      e.g. `agents` or `stats` remain usable; longer keys also catch one edit.
+    - Writes made by wormhole itself (refresh, task creation) are not checked.
+*/
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "type", content = "values")]
+pub enum Kind {
+    String,
+    /// `true`/`false`, or the strings "true"/"false"
+    Bool,
+    /// One of a fixed set of strings
+    Enum(&'static [&'static str]),
+    /// An http(s) URL
+    Url,
+    /// A JIRA issue key, e.g. ACT-123
+    JiraKey,
+    /// A string or an array of strings
+    StringList,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeySpec {
+    pub name: &'static str,
+    #[serde(flatten)]
+    pub kind: Kind,
+    pub description: &'static str,
+}
+
+pub const KEYS: &[KeySpec] = &[
+    KeySpec {
+        name: "land-in",
+        kind: Kind::Enum(&["terminal", "terminal-only", "editor", "none"]),
+        description: "Application to focus when switching to the project (`wormhole project pin`)",
+    },
+    KeySpec {
+        name: "agent",
+        kind: Kind::Enum(&["claude", "codex", "aider"]),
+        description: "Agent CLI that resumes synced conversations (default claude)",
+    },
+    KeySpec {
+        name: "visibility",
+        kind: Kind::Enum(&["hidden"]),
+        description: "Hide the project from lists and the dashboard",
+    },
+    KeySpec {
+        name: "status",
+        kind: Kind::String,
+        description: "Task status, e.g. in-progress or done (synced from JIRA when linked)",
+    },
+    KeySpec {
+        name: "jira_key",
+        kind: Kind::JiraKey,
+        description: "JIRA issue the task is for",
+    },
+    KeySpec {
+        name: "task_type",
+        kind: Kind::Enum(&["review", "issue"]),
+        description: "Set when a task is created from a GitHub PR (review) or issue",
+    },
+    KeySpec {
+        name: "review_pr_url",
+        kind: Kind::Url,
+        description: "PR being reviewed by a review task",
+    },
+    KeySpec {
+        name: "review_pr_title",
+        kind: Kind::String,
+        description: "Title of `review_pr_url`, shown on the dashboard",
+    },
+    KeySpec {
+        name: "review_submitted",
+        kind: Kind::Bool,
+        description: "Whether you have reviewed `review_pr_url` (set by refresh)",
+    },
+    KeySpec {
+        name: "github_issue_url",
+        kind: Kind::Url,
+        description: "GitHub issue an issue task is for",
+    },
+    KeySpec {
+        name: "github_issue_number",
+        kind: Kind::String,
+        description: "Number of `github_issue_url`",
+    },
+    KeySpec {
+        name: "aux-repos",
+        kind: Kind::StringList,
+        description: "Other repos the task touches, shown in its status",
+    },
+    KeySpec {
+        name: "last-message",
+        kind: Kind::String,
+        description: "Last message received via the `echo` message",
+    },
+];
+
+pub fn spec(name: &str) -> Option<&'static KeySpec> {
+    KEYS.iter().find(|k| k.name == name)
+}
+
+impl Kind {
+    fn check(&self, value: &Value) -> Result<(), String> {
+        let s = value.as_str();
+        let ok = match self {
+            Kind::String => s.is_some(),
+            Kind::Bool => value.is_boolean() || matches!(s, Some("true" | "false")),
+            Kind::Enum(values) => s.is_some_and(|s| values.contains(&s)),
+            Kind::Url => s.is_some_and(|s| s.starts_with("https://") || s.starts_with("http://")),
+            Kind::JiraKey => s.is_some_and(is_jira_key),
+            Kind::StringList => {
+                s.is_some()
+                    || value
+                        .as_array()
+                        .is_some_and(|items| items.iter().all(|v| v.is_string()))
+            }
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("expected {}, got {}", self.describe(), value))
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Kind::String => "a string".to_string(),
+            Kind::Bool => "true or false".to_string(),
+            Kind::Enum(values) => format!("one of {}", values.join(", ")),
+            Kind::Url => "an http(s) URL".to_string(),
+            Kind::JiraKey => "a JIRA key like ACT-123".to_string(),
+            Kind::StringList => "a string or an array of strings".to_string(),
+        }
+    }
+}
+
+fn is_jira_key(s: &str) -> bool {
+    let Some((project, number)) = s.split_once('-') else {
+        return false;
+    };
+    project.starts_with(|c: char| c.is_ascii_uppercase())
+        && project
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Check a value about to be stored under `key`.
+pub fn validate(key: &str, value: &Value) -> Result<(), String> {
+    match spec(key) {
+        Some(spec) => spec
+            .kind
+            .check(value)
+            .map_err(|e| format!("Invalid value for '{}': {}", key, e)),
+        None => match near_miss(key) {
+            Some(known) => Err(format!(
+                "Unknown key '{}'; did you mean '{}'? (see `wormhole kv keys`)",
+                key, known
+            )),
+            None => Ok(()),
+        },
+    }
+}
+
+/// Known keys shorter than this are only matched up to case and `_`/`-`.
+const NEAR_MISS_MIN_LEN: usize = 7;
+
+/// A known key that `key` is probably a misspelling of.
+fn near_miss(key: &str) -> Option<&'static str> {
+    let normalize = |s: &str| s.to_lowercase().replace('_', "-");
+    let key = normalize(key);
+    KEYS.iter().map(|k| k.name).find(|name| {
+        let max = if name.len() >= NEAR_MISS_MIN_LEN {
+            1
+        } else {
+            0
+        };
+        edit_distance(&key, &normalize(name)) <= max
+    })
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(cur)
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_known_keys() {
+        assert!(validate("land-in", &json!("editor")).is_ok());
+        assert!(validate("land-in", &json!("vscode")).is_err());
+        assert!(validate("jira_key", &json!("ACT-123")).is_ok());
+        assert!(validate("jira_key", &json!("act-123")).is_err());
+        assert!(validate("aux-repos", &json!(["cli", "api"])).is_ok());
+        assert!(validate("aux-repos", &json!([1])).is_err());
+        assert!(validate("review_submitted", &json!("true")).is_ok());
+        assert!(validate("review_pr_url", &json!("github.com/a/b")).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_typos_of_known_keys() {
+        assert!(validate("land_in", &json!("editor"))
+            .unwrap_err()
+            .contains("did you mean 'land-in'"));
+        assert!(validate("Jira-Key", &json!("ACT-1")).is_err());
+        assert!(validate("langd-in", &json!("editor")).is_err());
+        assert!(validate("Status", &json!("done")).is_err());
+        assert!(validate("notes", &json!("anything")).is_ok());
+        // Distinct short keys one edit away from a known one are allowed.
+        assert!(validate("agents", &json!(["a"])).is_ok());
+        assert!(validate("stats", &json!({})).is_ok());
+        assert!(validate("build", &json!({"flags": []})).is_ok());
+    }
+}
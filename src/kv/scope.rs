@@ -0,0 +1,657 @@
+use hyper::{Body, Response, StatusCode};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{history, KvMap};
+use crate::project::{Project, ProjectKey};
+use crate::projects;
+
+/*
+    - Besides each project's own KV there are two inherited scopes:
+      - repo: `{git_common_dir}/wormhole/scope/repo.json`, shared by the repo
+        and all of its tasks (outside `kv/`, which holds only project files);
+      - global: `~/.wormhole/kv.json`, shared by every project.
+    - A key resolves to the first of project, repo, global that has it. Only
+      settings wormhole reads itself (`land-in`, `aux-repos`, `agent`) are
+      resolved, along with the `WORMHOLE_KV_*` variables of
+      batch runs and `--where kv.<key>`; plain KV reads return the project's
+      own value, and `?resolved=true` asks for the effective one.
+    - In `/kv/<project>/...` paths and `wormhole kv`, `@<repo>` names a repo
+      scope and `@global` the global scope; `@repo:<repo>` also names a repo
+      scope, for a repo called `global`.
+    - Scope files are saved like project KV files: locked, atomically, with
+      history beside them, picking up changes made on disk first.
+    - `SCOPES` is held only to look up and publish values, never across git,
+      file locks or other I/O, since `resolve` is on the hot path.
+*/
+
+lazy_static! {
+    static ref SCOPES: Mutex<Scopes> = Mutex::new(Scopes::default());
+}
+
+#[derive(Default)]
+struct Scopes {
+    global: KvMap,
+    /// repo name -> (KV file, values)
+    repos: HashMap<String, (PathBuf, KvMap)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Project,
+    Repo,
+    Global,
+}
+
+/// A `/kv` target that is a scope rather than a project: `@global` or `@<repo>`.
+pub enum ScopeTarget {
+    Global,
+    Repo(String),
+}
+
+impl ScopeTarget {
+    pub fn parse(s: &str) -> Option<Self> {
+        let rest = s.strip_prefix('@')?;
+        if let Some(repo) = rest.strip_prefix("repo:") {
+            return Some(ScopeTarget::Repo(repo.to_string()));
+        }
+        match rest {
+            "global" => Some(ScopeTarget::Global),
+            repo => Some(ScopeTarget::Repo(repo.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for ScopeTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScopeTarget::Global => write!(f, "@global"),
+            ScopeTarget::Repo(repo) if repo == "global" => write!(f, "@repo:{}", repo),
+            ScopeTarget::Repo(repo) => write!(f, "@{}", repo),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Resolved {
+    pub value: Value,
+    pub scope: Scope,
+}
+
+fn global_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".wormhole/kv.json"))
+}
+
+fn repo_file(project: &Project) -> PathBuf {
+    repo_file_in(&super::wormhole_dir(project))
+}
+
+fn repo_file_in(wormhole_dir: &Path) -> PathBuf {
+    wormhole_dir.join("scope").join("repo.json")
+}
+
+/// Load the global scope and the repo scope of every repo in `projects`.
+pub fn load(projects: &projects::Projects) {
+    let mut repos = HashMap::new();
+    for project in projects.all() {
+        let repo = project.repo_name.to_string();
+        if repos.contains_key(&repo) {
+            continue;
+        }
+        let path = repo_file(project);
+        let kv = super::read_kv_file(&path);
+        repos.insert(repo, (path, kv));
+    }
+    let global = global_file()
+        .map(|p| super::read_kv_file(&p))
+        .unwrap_or_default();
+    let mut scopes = SCOPES.lock().unwrap();
+    scopes.global = global;
+    scopes.repos = repos;
+}
+
+/// Load the scope of a repo with no open projects, if it is a known repo.
+fn ensure_loaded(target: &ScopeTarget) {
+    let ScopeTarget::Repo(repo) = target else {
+        return;
+    };
+    if SCOPES.lock().unwrap().repos.contains_key(repo) {
+        return;
+    }
+    let Some((_, path)) = crate::config::available_projects()
+        .into_iter()
+        .find(|(name, _)| name.as_str() == repo)
+    else {
+        return;
+    };
+    let path = repo_file_in(&crate::git::git_common_dir(&path).join("wormhole"));
+    let kv = super::read_kv_file(&path);
+    SCOPES
+        .lock()
+        .unwrap()
+        .repos
+        .entry(repo.clone())
+        .or_insert((path, kv));
+}
+
+/// The effective value of `key` for `project`, and the scope it came from.
+pub fn resolve(project: &Project, key: &str) -> Option<Resolved> {
+    if let Some(v) = project.kv.get(key) {
+        return Some(Resolved {
+            value: v.clone(),
+            scope: Scope::Project,
+        });
+    }
+    load_repo(project);
+    let scopes = SCOPES.lock().unwrap();
+    if let Some(v) = repo_kv(&scopes, project).and_then(|kv| kv.get(key)) {
+        return Some(Resolved {
+            value: v.clone(),
+            scope: Scope::Repo,
+        });
+    }
+    scopes.global.get(key).map(|v| Resolved {
+        value: v.clone(),
+        scope: Scope::Global,
+    })
+}
+
+/// Every effective value for `project`: its own KV over its repo's over the
+/// global scope's.
+pub fn resolved_kv(project: &Project) -> KvMap {
+    load_repo(project);
+    let scopes = SCOPES.lock().unwrap();
+    let mut kv = scopes.global.clone();
+    kv.extend(repo_kv(&scopes, project).cloned().unwrap_or_default());
+    kv.extend(project.kv.clone());
+    kv
+}
+
+/// Load the repo scope of `project` on first use.
+fn load_repo(project: &Project) {
+    let repo = project.repo_name.to_string();
+    if SCOPES.lock().unwrap().repos.contains_key(&repo) {
+        return;
+    }
+    let path = repo_file(project);
+    let kv = super::read_kv_file(&path);
+    SCOPES
+        .lock()
+        .unwrap()
+        .repos
+        .entry(repo)
+        .or_insert((path, kv));
+}
+
+fn repo_kv<'a>(scopes: &'a Scopes, project: &Project) -> Option<&'a KvMap> {
+    scopes
+        .repos
+        .get(project.repo_name.as_str())
+        .map(|(_, kv)| kv)
+}
+
+/// Set a repo scope's values without a file, for tests elsewhere.
+#[cfg(test)]
+pub(crate) fn set_repo_for_test(repo: &str, kv: KvMap) {
+    SCOPES
+        .lock()
+        .unwrap()
+        .repos
+        .insert(repo.to_string(), (PathBuf::new(), kv));
+}
+
+/// Apply `f` to a scope's values and save them if it changed them, as one
+/// locked read-modify-write of the scope file like `super::modify`.
+fn modify<T>(
+    target: &ScopeTarget,
+    source: &str,
+    f: impl FnOnce(&mut KvMap) -> T,
+) -> Result<T, String> {
+    modify_recorded(
+        target,
+        |_, kv| f(kv),
+        |path, before, after, _| history::record_diff(path, source, before, after),
+    )
+}
+
+/// `modify`, with `f` also given the scope's file, and `record` writing the
+/// history of the change (from `before` to `after`) once it is saved.
+pub(super) fn modify_recorded<T>(
+    target: &ScopeTarget,
+    f: impl FnOnce(&Path, &mut KvMap) -> T,
+    record: impl FnOnce(&Path, &KvMap, &KvMap, &T),
+) -> Result<T, String> {
+    ensure_loaded(target);
+    let path = file(target).ok_or_else(|| match target {
+        ScopeTarget::Global => "No home directory".to_string(),
+        ScopeTarget::Repo(repo) => format!("Repo '{}' not found", repo),
+    })?;
+    super::with_lock(&path, || {
+        let mut kv = snapshot(target).unwrap_or_default();
+        if let Some(disk) = super::try_read_kv_file(&path) {
+            if disk != kv {
+                history::record_diff(&path, "external", &kv, &disk);
+                publish(target, disk.clone());
+                kv = disk;
+            }
+        }
+        let before = kv.clone();
+        let result = f(&path, &mut kv);
+        if kv == before {
+            return Ok(result);
+        }
+        super::backup_if_corrupt(&path);
+        let saved = if kv.is_empty() {
+            super::remove_durably(&path)
+        } else {
+            super::write_atomic(&path, &serde_json::to_string_pretty(&kv).unwrap())
+        };
+        if let Err(e) = saved {
+            return Err(format!("Failed to save {}: {}", path.display(), e));
+        }
+        publish(target, kv.clone());
+        record(&path, &before, &kv, &result);
+        Ok(result)
+    })?
+}
+
+/// Replace a scope's in-memory values.
+fn publish(target: &ScopeTarget, kv: KvMap) {
+    let mut scopes = SCOPES.lock().unwrap();
+    match target {
+        ScopeTarget::Global => scopes.global = kv,
+        ScopeTarget::Repo(repo) => {
+            if let Some((_, values)) = scopes.repos.get_mut(repo) {
+                *values = kv;
+            }
+        }
+    }
+}
+
+/// Make a scope's in-memory values match its file after a change on disk,
+/// recording the differences as `external`. Returns true if anything changed.
+pub(super) fn reload(target: &ScopeTarget) -> bool {
+    let Some(path) = loaded_file(target) else {
+        return false;
+    };
+    let Some(disk) = super::watch::read_external(&path) else {
+        return false;
+    };
+    let Some(kv) = snapshot(target) else {
+        return false;
+    };
+    if kv == disk {
+        return false;
+    }
+    history::record_diff(&path, "external", &kv, &disk);
+    publish(target, disk);
+    true
+}
+
+/// The files of the global scope and every loaded repo scope.
+pub(super) fn files() -> Vec<(ScopeTarget, PathBuf)> {
+    let scopes = SCOPES.lock().unwrap();
+    let repos = scopes
+        .repos
+        .iter()
+        .map(|(repo, (path, _))| (ScopeTarget::Repo(repo.clone()), path.clone()));
+    global_file()
+        .map(|path| (ScopeTarget::Global, path))
+        .into_iter()
+        .chain(repos)
+        .collect()
+}
+
+fn read<T>(target: &ScopeTarget, f: impl FnOnce(&KvMap) -> T) -> Option<T> {
+    ensure_loaded(target);
+    let scopes = SCOPES.lock().unwrap();
+    match target {
+        ScopeTarget::Global => Some(f(&scopes.global)),
+        ScopeTarget::Repo(repo) => scopes.repos.get(repo).map(|(_, kv)| f(kv)),
+    }
+}
+
+/// The file of a scope, or None for an unknown repo.
+pub(super) fn file(target: &ScopeTarget) -> Option<PathBuf> {
+    ensure_loaded(target);
+    loaded_file(target)
+}
+
+/// The file of a scope, or None for a repo whose scope isn't loaded.
+fn loaded_file(target: &ScopeTarget) -> Option<PathBuf> {
+    match target {
+        ScopeTarget::Global => global_file(),
+        ScopeTarget::Repo(repo) => SCOPES
+            .lock()
+            .unwrap()
+            .repos
+            .get(repo)
+            .map(|(path, _)| path.clone()),
+    }
+}
+
+/// A copy of a scope's values, or None for an unknown repo.
+pub fn snapshot(target: &ScopeTarget) -> Option<KvMap> {
+    read(target, |kv| kv.clone())
+}
+
+/// Set `entries` in a scope.
+pub fn set_all(
+    target: &ScopeTarget,
+    source: &str,
+    entries: Vec<(String, Value)>,
+) -> Result<(), String> {
+    modify(target, source, |kv| kv.extend(entries))
+}
+
+/// A 404 response if `target` is a repo wormhole doesn't know.
+fn unknown_repo(target: &ScopeTarget) -> Option<Response<Body>> {
+    let ScopeTarget::Repo(repo) = target else {
+        return None;
+    };
+    ensure_loaded(target);
+    if SCOPES.lock().unwrap().repos.contains_key(repo) {
+        return None;
+    }
+    Some(status_response(
+        StatusCode::NOT_FOUND,
+        format!("Repo '{}' not found", repo),
+    ))
+}
+
+fn status_response(status: StatusCode, msg: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(msg))
+        .unwrap()
+}
+
+/// `{"value": ..., "scope": ...}` for the effective value of `kv_key`.
+pub fn get_resolved(key: &ProjectKey, kv_key: &str) -> Response<Body> {
+    let Some(project) = projects::lock().by_key(key) else {
+        return status_response(
+            StatusCode::NOT_FOUND,
+            format!("Project '{}' not found", key),
+        );
+    };
+    match resolve(&project, kv_key) {
+        Some(resolved) => Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_string(&resolved).unwrap()))
+            .unwrap(),
+        None => status_response(
+            StatusCode::NOT_FOUND,
+            format!("Key '{}' not set for '{}' or inherited", kv_key, key),
+        ),
+    }
+}
+
+pub fn get_scope_kv(target: &ScopeTarget) -> Response<Body> {
+    match read(target, |kv| serde_json::to_string_pretty(kv).unwrap()) {
+        Some(json) => Response::new(Body::from(json)),
+        None => status_response(StatusCode::NOT_FOUND, "Scope not found".to_string()),
+    }
+}
+
+pub fn get_value(
+    target: &ScopeTarget,
+    kv_key: &str,
+    pointer: Option<&str>,
+    json: bool,
+) -> Response<Body> {
+    if let Some(response) = super::invalid_pointer(pointer) {
+        return response;
+    }
+    let value = read(target, |kv| {
+        kv.get(kv_key)
+            .and_then(|v| v.pointer(pointer.unwrap_or("")))
+            .cloned()
+    })
+    .flatten();
+    super::value_response(value.as_ref(), json, || {
+        format!("Key '{}{}' not found", kv_key, pointer.unwrap_or(""))
+    })
+}
+
+pub async fn set_value(
+    target: &ScopeTarget,
+    source: &str,
+    kv_key: &str,
+    pointer: Option<&str>,
+    json: bool,
+    body: Body,
+) -> Response<Body> {
+    if let Some(response) = unknown_repo(target) {
+        return response;
+    }
+    let bytes = hyper::body::to_bytes(body).await.unwrap_or_default();
+    let value = match super::body_value(&bytes, json) {
+        Ok(v) => v,
+        Err(e) => return status_response(StatusCode::BAD_REQUEST, e),
+    };
+    let result = modify(target, source, |kv| {
+        let mut root = kv.get(kv_key).cloned().unwrap_or(Value::Null);
+        super::set_pointer(&mut root, pointer.unwrap_or(""), value)
+            .and_then(|()| super::schema::validate(kv_key, &root))?;
+        Ok(kv.insert(kv_key.to_string(), root.clone()) != Some(root))
+    });
+    match result {
+        Err(e) => status_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Ok(Err(e)) => status_response(StatusCode::BAD_REQUEST, e),
+        Ok(Ok(changed)) => {
+            if changed {
+                projects::notify_state_change();
+            }
+            Response::new(Body::empty())
+        }
+    }
+}
+
+pub fn delete_value(
+    target: &ScopeTarget,
+    source: &str,
+    kv_key: &str,
+    pointer: Option<&str>,
+) -> Response<Body> {
+    if let Some(response) = super::invalid_pointer(pointer).or_else(|| unknown_repo(target)) {
+        return response;
+    }
+    let result = modify(target, source, |kv| match pointer {
+        Some(p) => kv
+            .get_mut(kv_key)
+            .is_some_and(|v| super::remove_pointer(v, p)),
+        None => kv.remove(kv_key).is_some(),
+    });
+    match result {
+        Ok(true) => {
+            projects::notify_state_change();
+            Response::new(Body::empty())
+        }
+        Ok(false) => status_response(StatusCode::NOT_FOUND, format!("Key '{}' not found", kv_key)),
+        Err(e) => status_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CanonicalName;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn test_resolve_project_then_repo_then_global() {
+        let project = Project {
+            repo_name: CanonicalName::for_test("scope-test-repo"),
+            repo_path: "/nonexistent".into(),
+            branch: None,
+            kv: KvMap::from([("land-in".to_string(), json!("editor"))]),
+            cached: Default::default(),
+        };
+        {
+            let mut scopes = SCOPES.lock().unwrap();
+            scopes.repos.insert(
+                "scope-test-repo".to_string(),
+                (
+                    PathBuf::new(),
+                    KvMap::from([
+                        ("land-in".to_string(), json!("terminal")),
+                        ("aux-repos".to_string(), json!(["a"])),
+                    ]),
+                ),
+            );
+            scopes.global.insert("scope-test-key".to_string(), json!(1));
+        }
+        let r = resolve(&project, "land-in").unwrap();
+        assert_eq!((r.value, r.scope), (json!("editor"), Scope::Project));
+        let r = resolve(&project, "aux-repos").unwrap();
+        assert_eq!((r.value, r.scope), (json!(["a"]), Scope::Repo));
+        let r = resolve(&project, "scope-test-key").unwrap();
+        assert_eq!((r.value, r.scope), (json!(1), Scope::Global));
+        assert!(resolve(&project, "missing").is_none());
+        let kv = resolved_kv(&project);
+        assert_eq!(kv["land-in"], json!("editor"));
+        assert_eq!(kv["aux-repos"], json!(["a"]));
+        assert_eq!(kv["scope-test-key"], json!(1));
+    }
+
+    #[test]
+    fn test_modify_saves_with_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = repo_file_in(dir.path());
+        let target = ScopeTarget::Repo("scope-modify-test-repo".to_string());
+        SCOPES.lock().unwrap().repos.insert(
+            "scope-modify-test-repo".to_string(),
+            (path.clone(), KvMap::new()),
+        );
+
+        modify(&target, "cli", |kv| kv.insert("a".into(), json!(1))).unwrap();
+        assert_eq!(super::super::read_kv_file(&path)["a"], json!(1));
+        super::super::write_atomic(&path, r#"{"a": 1, "b": 2}"#).unwrap();
+        modify(&target, "cli", |kv| kv.remove("a")).unwrap();
+
+        assert_eq!(
+            snapshot(&target).unwrap(),
+            KvMap::from([("b".to_string(), json!(2))])
+        );
+        let history = std::fs::read_to_string(path.with_extension("history.jsonl")).unwrap();
+        let changes: Vec<(String, String)> = history
+            .lines()
+            .map(|l| serde_json::from_str::<history::Change>(l).unwrap())
+            .map(|c| (c.source, c.key))
+            .collect();
+        let expected = [("cli", "a"), ("external", "b"), ("cli", "a")];
+        assert_eq!(
+            changes,
+            expected.map(|(s, k)| (s.to_string(), k.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_undo_scope_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = repo_file_in(dir.path());
+        let target = ScopeTarget::Repo("scope-undo-test-repo".to_string());
+        SCOPES.lock().unwrap().repos.insert(
+            "scope-undo-test-repo".to_string(),
+            (path.clone(), KvMap::new()),
+        );
+        modify(&target, "cli", |kv| kv.insert("a".into(), json!(1))).unwrap();
+        modify(&target, "cli", |kv| kv.insert("a".into(), json!(2))).unwrap();
+
+        let response = history::undo_scope(&target, Some("a"));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(snapshot(&target).unwrap()["a"], json!(1));
+        assert_eq!(super::super::read_kv_file(&path)["a"], json!(1));
+        let changes = history::read_at(&path);
+        let last = changes.last().unwrap();
+        assert_eq!((last.source.as_str(), last.undoes), ("undo", Some(2)));
+        assert_eq!(changes.len(), 3);
+
+        let unknown = ScopeTarget::Repo("scope-no-such-undo-test-repo".to_string());
+        let response = history::undo_scope(&unknown, None);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_delete_reports_unknown_repo_and_failed_save() {
+        let unknown = ScopeTarget::Repo("scope-no-such-test-repo".to_string());
+        let response = delete_value(&unknown, "cli", "a", None);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // A scope file under a regular file can't be saved
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("file"), "").unwrap();
+        let path = repo_file_in(&dir.path().join("file"));
+        SCOPES.lock().unwrap().repos.insert(
+            "scope-unwritable-test-repo".to_string(),
+            (
+                path,
+                KvMap::from([("a".to_string(), json!(1)), ("b".to_string(), json!(2))]),
+            ),
+        );
+        let target = ScopeTarget::Repo("scope-unwritable-test-repo".to_string());
+        let response = delete_value(&target, "cli", "a", None);
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        // A directory where the scope file should be can't be replaced
+        let path = repo_file_in(dir.path());
+        std::fs::create_dir_all(path.join("subdir")).unwrap();
+        SCOPES.lock().unwrap().repos.insert(
+            "scope-unsavable-test-repo".to_string(),
+            (path.clone(), KvMap::from([("a".to_string(), json!(1))])),
+        );
+        let target = ScopeTarget::Repo("scope-unsavable-test-repo".to_string());
+        let response = delete_value(&target, "cli", "a", None);
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(snapshot(&target).unwrap()["a"], json!(1));
+        assert_eq!(super::history::mark(&path), 0);
+    }
+
+    #[test]
+    fn test_reload_treats_deleted_file_as_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = repo_file_in(dir.path());
+        let target = ScopeTarget::Repo("scope-reload-test-repo".to_string());
+        SCOPES.lock().unwrap().repos.insert(
+            "scope-reload-test-repo".to_string(),
+            (path.clone(), KvMap::new()),
+        );
+
+        super::super::write_atomic(&path, r#"{"a": 2}"#).unwrap();
+        assert!(reload(&target));
+        assert!(!reload(&target));
+        assert_eq!(snapshot(&target).unwrap()["a"], json!(2));
+        fs::write(&path, r#"{"a": "#).unwrap();
+        assert!(!reload(&target));
+        assert_eq!(snapshot(&target).unwrap()["a"], json!(2));
+        fs::remove_file(&path).unwrap();
+        assert!(reload(&target));
+        assert_eq!(snapshot(&target).unwrap(), KvMap::new());
+    }
+
+    #[test]
+    fn test_scope_target_parse() {
+        assert!(matches!(
+            ScopeTarget::parse("@global"),
+            Some(ScopeTarget::Global)
+        ));
+        assert!(matches!(
+            ScopeTarget::parse("@cli"),
+            Some(ScopeTarget::Repo(r)) if r == "cli"
+        ));
+        assert!(ScopeTarget::parse("cli:branch").is_none());
+
+        // A repo called `global` is addressed as `@repo:global`
+        let global_repo = ScopeTarget::parse("@repo:global").unwrap();
+        assert!(matches!(&global_repo, ScopeTarget::Repo(r) if r == "global"));
+        assert_eq!(global_repo.to_string(), "@repo:global");
+        assert_eq!(ScopeTarget::Global.to_string(), "@global");
+        assert_eq!(ScopeTarget::Repo("cli".into()).to_string(), "@cli");
+    }
+}
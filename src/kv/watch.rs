@@ -0,0 +1,175 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::scope;
+use super::KvMap;
+use crate::project::{Project, ProjectKey};
+use crate::{git, projects};
+
+/*
+    - KV files can change behind the server's back: scripts, the CLI writing
+      directly, or another machine syncing the repo.
+    - Every POLL_INTERVAL, stat each known project's KV file and each loaded
+      scope file. When its mtime changes, re-read it and apply any differences
+      to the in-memory values, recording them in KV history with source
+      `external`.
+    - Files are read under the projects lock, which the server also holds while
+      writing, so a reload never races with (and reverts) a server write. The
+      server's own writes therefore reload as no-ops.
+    - A file that has been deleted is treated as empty: every key it held is
+      recorded as removed. Writers replace files by renaming, so a file is
+      never briefly absent, and one is only moved aside as `.corrupt` by a
+      save that writes it again straight after.
+    - Corrupt and unreadable files are skipped rather than treated as empty,
+      so that they never wipe the in-memory values.
+*/
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Watch KV files forever. Spawned on a thread at server startup.
+pub fn run() {
+    let mut watcher = Watcher::default();
+    loop {
+        watcher.poll();
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[derive(Default)]
+struct Watcher {
+    /// repo path -> KV directory, since resolving it runs git
+    kv_dirs: HashMap<PathBuf, PathBuf>,
+    mtimes: HashMap<PathBuf, SystemTime>,
+    primed: bool,
+}
+
+impl Watcher {
+    fn poll(&mut self) {
+        let targets: Vec<(ProjectKey, PathBuf)> = projects::lock()
+            .all()
+            .iter()
+            .map(|p| (p.store_key(), p.repo_path.clone()))
+            .collect();
+
+        let mut changed = Vec::new();
+        let mut changed_scopes = Vec::new();
+        for (key, repo_path) in targets {
+            let dir = self
+                .kv_dirs
+                .entry(repo_path)
+                .or_insert_with_key(|repo_path| {
+                    git::git_common_dir(repo_path).join("wormhole").join("kv")
+                });
+            let path = dir.join(super::kv_file_name(&key));
+            if self.modified(&path) {
+                changed.push((key, path));
+            }
+        }
+        for (target, path) in scope::files() {
+            if self.modified(&path) {
+                changed_scopes.push(target);
+            }
+        }
+        self.primed = true;
+
+        let mut any = false;
+        for (key, path) in changed {
+            let mut projects = projects::lock();
+            let Some(kv) = read_external(&path) else {
+                continue;
+            };
+            if let Some(project) = projects.get_mut(&key) {
+                any |= apply_external(project, kv);
+            }
+        }
+        for target in changed_scopes {
+            any |= scope::reload(&target);
+        }
+        if any {
+            projects::notify_state_change();
+        }
+    }
+
+    /// Record `path`'s mtime; true if it changed since the last poll. The
+    /// first poll only records mtimes: `load_kv_data` has just run.
+    fn modified(&mut self, path: &PathBuf) -> bool {
+        let mtime = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let previous = match mtime {
+            Some(t) => self.mtimes.insert(path.clone(), t),
+            None => self.mtimes.remove(path),
+        };
+        self.primed && previous != mtime
+    }
+}
+
+/// The KV in the file at `path` after an external change: empty if the file
+/// was deleted, None if it can't be read or parsed.
+pub(super) fn read_external(path: &Path) -> Option<KvMap> {
+    match fs::read_to_string(path) {
+        Ok(data) => serde_json::from_str(&data).ok(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Some(KvMap::new()),
+        Err(_) => None,
+    }
+}
+
+/// Make `project.kv` equal to `kv`, recording each differing key. Returns true
+/// if anything changed.
+pub(super) fn apply_external(project: &mut Project, kv: KvMap) -> bool {
+    let keys: BTreeSet<String> = project.kv.keys().chain(kv.keys()).cloned().collect();
+    let mut changed = false;
+    for key in keys {
+        changed |= super::update(project, "external", &key, kv.get(&key).cloned());
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CanonicalName;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_external_diffs_and_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut project = Project {
+            repo_name: CanonicalName::for_test("repo"),
+            repo_path: dir.path().to_path_buf(),
+            branch: None,
+            kv: KvMap::from([
+                ("keep".to_string(), json!("same")),
+                ("gone".to_string(), json!("x")),
+            ]),
+            cached: Default::default(),
+        };
+        let external = KvMap::from([
+            ("keep".to_string(), json!("same")),
+            ("new".to_string(), json!([1, 2])),
+        ]);
+        assert!(apply_external(&mut project, external.clone()));
+        assert_eq!(project.kv, external);
+        assert!(!apply_external(&mut project, external));
+
+        let history =
+            fs::read_to_string(dir.path().join(".git/wormhole/kv/repo.history.jsonl")).unwrap();
+        let sources: Vec<serde_json::Value> = history
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(sources.len(), 2);
+        assert!(sources.iter().all(|c| c["source"] == "external"));
+    }
+
+    #[test]
+    fn test_read_external_treats_deleted_file_as_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repo.json");
+        assert_eq!(read_external(&path), Some(KvMap::new()));
+        fs::write(&path, r#"{"a": 1}"#).unwrap();
+        assert_eq!(read_external(&path).unwrap()["a"], json!(1));
+        fs::write(&path, r#"{"a": "#).unwrap();
+        assert_eq!(read_external(&path), None);
+    }
+}
@@ -13,6 +13,8 @@ mod messages;
 mod project;
 mod project_path;
 mod projects;
+mod schedule;
+mod selector;
 mod serve_web;
 mod status;
 mod task;
@@ -52,6 +54,9 @@ async fn main() {
             projects::load();
             // Refresh cache in background so server starts immediately
             std::thread::spawn(projects::refresh_cache);
+            std::thread::spawn(schedule::run);
+            std::thread::spawn(kv::watch::run);
+            std::thread::spawn(conversations::watch::run);
             serve_http().await;
         }
         // Other subcommands -> run as client
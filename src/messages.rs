@@ -95,7 +95,7 @@ impl<'a> Store<'a> {
         self.0
             .consumers
             .get(&id)
-            .map_or(false, |c| !c.queue.is_empty())
+            .is_some_and(|c| !c.queue.is_empty())
     }
 
     #[allow(dead_code)]
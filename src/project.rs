@@ -3,7 +3,6 @@ use crate::editor::Editor;
 use crate::git;
 use crate::project_path::ProjectPath;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::fmt;
 use std::path::PathBuf;
 
@@ -119,7 +118,7 @@ pub struct Project {
     pub branch: Option<BranchName>,
 
     // User-persisted preferences (from .git/wormhole/kv/)
-    pub kv: HashMap<String, String>,
+    pub kv: crate::kv::KvMap,
 
     // Derived data (refreshed by `wormhole refresh`)
     pub cached: Cached,
@@ -137,8 +136,23 @@ impl Project {
     }
 
     pub fn is_hidden(&self) -> bool {
-        self.kv.get("visibility").is_some_and(|v| v == "hidden")
-            || self.kv.get("status").is_some_and(|v| v == "done")
+        self.kv_str("visibility") == Some("hidden") || self.kv_str("status") == Some("done")
+    }
+
+    /// A KV value, if present and a string.
+    pub fn kv_str(&self, key: &str) -> Option<&str> {
+        self.kv.get(key).and_then(|v| v.as_str())
+    }
+
+    /// A KV value inherited from the repo or global scope unless set on this
+    /// project, if present and a string.
+    pub fn kv_resolved_str(&self, key: &str) -> Option<String> {
+        crate::kv::scope::resolve(self, key).and_then(|r| r.value.as_str().map(|s| s.to_string()))
+    }
+
+    /// True if a KV value is `true` or the string `"true"`.
+    pub fn kv_flag(&self, key: &str) -> bool {
+        self.kv.get(key).is_some_and(|v| v == true || v == "true")
     }
 
     pub fn has_jira(&self) -> bool {
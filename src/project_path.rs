@@ -36,7 +36,9 @@ impl ProjectPath {
         // (or explicit --land-in) opts into it. With nothing specified, default
         // to terminal-only — a pure tmux spawn, no editor, no workspace file.
         let land_in = land_in
-            .or_else(|| crate::wormhole::parse_land_in(self.project.kv.get("land-in")))
+            .or_else(|| {
+                crate::wormhole::parse_land_in(self.project.kv_resolved_str("land-in").as_deref())
+            })
             .or_else(|| {
                 if is_already_open && is_explicit_switch {
                     current_app.map(LandIn::from)
@@ -375,8 +375,8 @@ pub fn refresh_tasks() {
             projects.0.ring.push_back(key.clone());
             changed = true;
         }
-        if !projects.0.all.contains_key(&key) {
-            projects.0.all.insert(key, project);
+        if let std::collections::hash_map::Entry::Vacant(e) = projects.0.all.entry(key) {
+            e.insert(project);
             changed = true;
         }
     }
@@ -408,57 +408,65 @@ pub fn refresh_cache() {
             .iter()
             .filter(|(_, p)| p.is_task())
             .map(|(key, p)| {
-                let jira_key = p.kv.get("jira_key").cloned();
-                let review_pr =
-                    p.kv.get("review_pr_url")
-                        .and_then(|u| github::parse_github_ref(u));
+                let jira_key = p.kv_str("jira_key").map(String::from);
+                let review_pr = p.kv_str("review_pr_url").and_then(github::parse_github_ref);
                 let path = p.working_tree();
                 (key.clone(), jira_key, review_pr, path)
             })
             .collect()
     };
 
-    let results: Vec<_> = task_info
+    let (jira, github_queries): (Vec<_>, Vec<_>) = task_info
         .par_iter()
         .map(|(key, jira_key, review_pr, path)| {
             let jira = jira_key
                 .as_ref()
                 .and_then(|k| jira::get_issue(k).ok().flatten());
-            let pr = github::get_pr_status(path);
-            let review_submitted = review_pr
-                .as_ref()
-                .map(|r| github::has_my_review(&r.owner, &r.repo, r.number));
-            (key.clone(), jira, pr, review_submitted)
+            let query = github::TaskQuery {
+                repo: crate::git::github_repo_from_remote(path),
+                branch: key.branch.as_ref().map(|b| b.to_string()),
+                review_pr: review_pr.clone(),
+            };
+            (jira, query)
         })
-        .collect();
+        .unzip();
+    // On failure keep the cached PRs rather than clearing them all
+    let github: Vec<_> = match github::task_statuses(github::client(), &github_queries) {
+        Ok(statuses) => statuses.into_iter().map(Some).collect(),
+        Err(e) => {
+            eprintln!("GitHub refresh failed: {}", e);
+            github_queries.iter().map(|_| None).collect()
+        }
+    };
 
     {
         let mut projects = lock();
-        for (key, jira, pr, review_submitted) in results {
-            if let Some(project) = projects.0.all.get_mut(&key) {
+        for (((key, ..), jira), github) in task_info.iter().zip(jira).zip(github) {
+            if let Some(project) = projects.0.all.get_mut(key) {
+                let mut updates = vec![];
                 if let Some(ref j) = jira {
-                    let jira_status = jira_status_to_local(&j.status);
-                    match jira_status {
-                        Some(s) => {
-                            project.kv.insert("status".to_string(), s.to_string());
-                        }
-                        None => {
-                            project.kv.remove("status");
-                        }
-                    }
+                    let status = jira_status_to_local(&j.status).map(|s| s.into());
+                    updates.push(("status", status));
                 }
-                if let Some(reviewed) = review_submitted {
-                    if reviewed {
-                        project
-                            .kv
-                            .insert("review_submitted".to_string(), "true".to_string());
-                    } else {
-                        project.kv.remove("review_submitted");
+                if let Some(reviewed) = github.as_ref().and_then(|g| g.review_submitted) {
+                    updates.push(("review_submitted", reviewed.then(|| "true".into())));
+                }
+                // Only lock and save the KV file of tasks whose values changed
+                updates.retain(|(k, v)| project.kv.get(*k) != v.as_ref());
+                if !updates.is_empty() {
+                    let result = crate::kv::modify(project, |project| {
+                        for (k, v) in updates {
+                            crate::kv::update(project, "refresh", k, v);
+                        }
+                    });
+                    if let Err(e) = result {
+                        eprintln!("Failed to save refreshed KV for {}: {}", key, e);
                     }
                 }
-                crate::kv::save_project_kv_pub(project);
                 project.cached.jira = jira;
-                project.cached.pr = pr;
+                if let Some(github) = github {
+                    project.cached.pr = github.pr;
+                }
             }
         }
     }
@@ -473,12 +481,25 @@ pub fn refresh_cache() {
 const CARD_GENERATED_BEGIN: &str = "<!-- wormhole:generated:begin -->";
 const CARD_GENERATED_END: &str = "<!-- wormhole:generated:end -->";
 
+/// Write the generated section of each task's `.task/card.md`: the output of
+/// `card_commands`, then, with `card_conversations`, a summary of the task's
+/// synced conversations. Cards are only written when one of these is set.
 fn generate_cards(task_info: &[(ProjectKey, Option<String>, std::path::PathBuf)]) {
     let commands = crate::config::reload_card_commands();
-    if commands.is_empty() {
+    let with_conversations = crate::config::reload_card_conversations();
+    if commands.is_empty() && !with_conversations {
         return;
     }
-    task_info.par_iter().for_each(|(_, _, path)| {
+    task_info.par_iter().for_each(|(key, _, path)| {
+        let conversations = with_conversations
+            .then(|| {
+                let sessions = crate::conversations::timeline::task_sessions(&key.to_string());
+                crate::conversations::timeline::card_summary(&sessions, path)
+            })
+            .flatten();
+        if commands.is_empty() && conversations.is_none() {
+            return;
+        }
         let task_dir = path.join(".task");
         let _ = std::fs::create_dir_all(&task_dir);
         let mut sections = Vec::new();
@@ -500,6 +521,7 @@ fn generate_cards(task_info: &[(ProjectKey, Option<String>, std::path::PathBuf)]
                 Err(e) => sections.push(format!("```\nerror: {}\n```", e)),
             }
         }
+        sections.extend(conversations);
         let generated = format!(
             "{}\n{}\n{}",
             CARD_GENERATED_BEGIN,
@@ -0,0 +1,396 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::batch::{self, BatchRequest, BatchSummary, RunSpec};
+use crate::config::{self, ScheduleEntry};
+use crate::projects;
+
+/*
+    - Schedules are `[[schedules]]` entries in wormhole.toml, re-read every tick
+      so edits take effect without a restart.
+    - Once a minute, each schedule whose cron expression matches the current
+      local time creates a batch via `batch::create_batch`.
+    - The IDs of the last `keep` batches per schedule are retained; older
+      completed batches are removed from the batch store along with their output.
+      An older batch that is still running stays in the history until a later
+      fire finds it finished, so long runs are never leaked from the store.
+*/
+
+lazy_static! {
+    static ref HISTORY: Mutex<HashMap<String, VecDeque<String>>> = Mutex::new(HashMap::new());
+}
+
+/// A parsed 5-field cron expression. Each field is a bitset of allowed values.
+#[derive(Debug, PartialEq)]
+pub struct Cron {
+    minute: u64,
+    hour: u64,
+    day: u64,
+    month: u64,
+    weekday: u64,
+    day_restricted: bool,
+    weekday_restricted: bool,
+}
+
+/// The calendar fields of a local time that a cron expression matches against.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalTime {
+    pub minute: u32,
+    pub hour: u32,
+    pub day: u32,
+    pub month: u32,
+    /// 0 = Sunday
+    pub weekday: u32,
+}
+
+impl LocalTime {
+    pub fn from_system_time(t: SystemTime) -> Self {
+        let secs = t
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as libc::time_t;
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        unsafe {
+            libc::localtime_r(&secs, &mut tm);
+        }
+        LocalTime {
+            minute: tm.tm_min as u32,
+            hour: tm.tm_hour as u32,
+            day: tm.tm_mday as u32,
+            month: tm.tm_mon as u32 + 1,
+            weekday: tm.tm_wday as u32,
+        }
+    }
+}
+
+impl Cron {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(format!(
+                "cron expression '{}' must have 5 fields (minute hour day month weekday)",
+                expr
+            ));
+        };
+        // Both 0 and 7 mean Sunday.
+        let mut weekday_bits = parse_field(weekday, 0, 7)?;
+        if weekday_bits & (1 << 7) != 0 {
+            weekday_bits = (weekday_bits | 1) & !(1 << 7);
+        }
+        Ok(Cron {
+            minute: parse_field(minute, 0, 59)?,
+            hour: parse_field(hour, 0, 23)?,
+            day: parse_field(day, 1, 31)?,
+            month: parse_field(month, 1, 12)?,
+            weekday: weekday_bits,
+            // As in cron(8), a field starting with `*` (e.g. `*/2`) is unrestricted.
+            day_restricted: !day.starts_with('*'),
+            weekday_restricted: !weekday.starts_with('*'),
+        })
+    }
+
+    pub fn matches(&self, t: &LocalTime) -> bool {
+        let bit = |set: u64, v: u32| set & (1 << v) != 0;
+        let day_ok = bit(self.day, t.day);
+        let weekday_ok = bit(self.weekday, t.weekday);
+        // As in cron(8): when both day fields are restricted, either may match.
+        let date_ok = if self.day_restricted && self.weekday_restricted {
+            day_ok || weekday_ok
+        } else {
+            day_ok && weekday_ok
+        };
+        bit(self.minute, t.minute) && bit(self.hour, t.hour) && bit(self.month, t.month) && date_ok
+    }
+}
+
+/// Parse one cron field: `*`, `N`, `N-M`, `*/S`, `N-M/S`, and comma lists thereof.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (
+                r,
+                s.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step in cron field '{}'", field))?,
+            ),
+            None => (part, 1),
+        };
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, field)?, parse_value(b, field)?)
+        } else {
+            let v = parse_value(range, field)?;
+            (v, v)
+        };
+        if lo < min || hi > max || lo > hi {
+            return Err(format!(
+                "cron field '{}' out of range {}-{}",
+                field, min, max
+            ));
+        }
+        let mut v = lo;
+        while v <= hi {
+            bits |= 1 << v;
+            v += step;
+        }
+    }
+    Ok(bits)
+}
+
+fn parse_value(s: &str, field: &str) -> Result<u32, String> {
+    s.parse()
+        .map_err(|_| format!("invalid value '{}' in cron field '{}'", s, field))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ScheduleStatus {
+    #[serde(flatten)]
+    pub entry: ScheduleEntry,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Most recent first
+    pub batches: Vec<BatchSummary>,
+}
+
+impl ScheduleStatus {
+    /// True if the most recent completed batch had failed runs.
+    pub fn is_failing(&self) -> bool {
+        self.batches
+            .iter()
+            .find(|b| b.done)
+            .is_some_and(|b| b.failed > 0)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ScheduleListResponse {
+    pub schedules: Vec<ScheduleStatus>,
+}
+
+impl ScheduleListResponse {
+    pub fn render_terminal(&self) -> String {
+        if self.schedules.is_empty() {
+            return "No schedules\n".to_string();
+        }
+        let mut out = String::new();
+        for s in &self.schedules {
+            let state = match (&s.error, s.batches.first()) {
+                (Some(e), _) => format!("invalid: {}", e),
+                (None, None) => "never run".to_string(),
+                (None, Some(b)) if !b.done => format!("running {}", b.id),
+                (None, Some(b)) if b.failed > 0 => {
+                    format!("FAILED {}/{} ({})", b.failed, b.total, b.id)
+                }
+                (None, Some(b)) => format!("ok ({})", b.id),
+            };
+            out.push_str(&format!(
+                "{} [{}] {}: {}\n",
+                s.entry.name, s.entry.cron, s.entry.command, state
+            ));
+        }
+        out
+    }
+}
+
+/// Run the scheduler loop forever. Spawned on a thread at server startup.
+pub fn run() {
+    loop {
+        let now = SystemTime::now();
+        let secs = now
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        std::thread::sleep(Duration::from_secs(60 - secs % 60));
+        tick(LocalTime::from_system_time(SystemTime::now()));
+    }
+}
+
+fn tick(now: LocalTime) {
+    for entry in config::reload_schedules() {
+        if Cron::parse(&entry.cron).is_ok_and(|c| c.matches(&now)) {
+            fire(&entry);
+        }
+    }
+}
+
+/// Create and start a batch for `entry`, returning its ID. Returns `None` if
+/// no projects match the schedule's filters.
+pub fn fire(entry: &ScheduleEntry) -> Option<String> {
+    let runs = run_specs(entry);
+    if runs.is_empty() {
+        return None;
+    }
+    let id = batch::create_batch(BatchRequest {
+        command: vec![entry.command.clone()],
+        runs,
+        schedule: Some(entry.name.clone()),
+        tmux: false,
+    });
+    batch::spawn_batch(&id);
+    batch::notify_change();
+    let mut store = batch::lock();
+    let evicted = record(&entry.name, &id, entry.keep, |id| {
+        store.get(id).is_none_or(|b| b.is_done())
+    });
+    for old in evicted {
+        store.remove(&old);
+    }
+    Some(id)
+}
+
+/// Append `id` to the schedule's history and return the finished IDs older
+/// than the newest `keep`, dropping them from the history. Older batches that
+/// are still running stay in the history.
+fn record(name: &str, id: &str, keep: usize, is_done: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut history = HISTORY.lock().unwrap();
+    let ids = history.entry(name.to_string()).or_default();
+    ids.push_back(id.to_string());
+    let older = ids.len().saturating_sub(keep.max(1));
+    let (evicted, running): (Vec<String>, Vec<String>) =
+        ids.drain(..older).partition(|id| is_done(id));
+    for id in running.into_iter().rev() {
+        ids.push_front(id);
+    }
+    evicted
+}
+
+fn run_specs(entry: &ScheduleEntry) -> Vec<RunSpec> {
+    let window_names = if entry.active {
+        crate::tmux::window_names()
+    } else {
+        vec![]
+    };
+    projects::lock()
+        .open()
+        .into_iter()
+        .filter(|p| !entry.tasks || p.is_task())
+        .filter(|p| !entry.active || p.is_active(&window_names))
+        .map(|p| RunSpec {
+            key: p.store_key().to_string(),
+            dir: p.working_tree(),
+        })
+        .collect()
+}
+
+pub fn list() -> ScheduleListResponse {
+    let history = HISTORY.lock().unwrap().clone();
+    let store = batch::lock();
+    let schedules = config::reload_schedules()
+        .into_iter()
+        .map(|entry| {
+            let batches = history
+                .get(&entry.name)
+                .map(|ids| {
+                    ids.iter()
+                        .rev()
+                        .filter_map(|id| store.get(id).map(|b| b.to_summary()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            ScheduleStatus {
+                error: Cron::parse(&entry.cron).err(),
+                entry,
+                batches,
+            }
+        })
+        .collect();
+    ScheduleListResponse { schedules }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minute: u32, hour: u32, day: u32, month: u32, weekday: u32) -> LocalTime {
+        LocalTime {
+            minute,
+            hour,
+            day,
+            month,
+            weekday,
+        }
+    }
+
+    #[test]
+    fn test_cron_daily() {
+        let c = Cron::parse("30 9 * * *").unwrap();
+        assert!(c.matches(&at(30, 9, 14, 3, 2)));
+        assert!(!c.matches(&at(31, 9, 14, 3, 2)));
+        assert!(!c.matches(&at(30, 10, 14, 3, 2)));
+    }
+
+    #[test]
+    fn test_cron_steps_ranges_and_lists() {
+        let c = Cron::parse("*/15 8-18 * * 1-5").unwrap();
+        assert!(c.matches(&at(45, 18, 1, 1, 5)));
+        assert!(
+            !c.matches(&at(40, 12, 1, 1, 3)),
+            "40 is not a multiple of 15"
+        );
+        assert!(!c.matches(&at(0, 12, 1, 1, 6)), "Saturday excluded");
+        let c = Cron::parse("0 0,12 * * *").unwrap();
+        assert!(c.matches(&at(0, 12, 1, 1, 0)));
+        assert!(!c.matches(&at(0, 6, 1, 1, 0)));
+    }
+
+    #[test]
+    fn test_cron_sunday_is_0_or_7() {
+        let c = Cron::parse("0 0 * * 7").unwrap();
+        assert!(c.matches(&at(0, 0, 1, 1, 0)));
+    }
+
+    #[test]
+    fn test_cron_day_or_weekday_when_both_restricted() {
+        let c = Cron::parse("0 0 1 * 1").unwrap();
+        assert!(c.matches(&at(0, 0, 1, 1, 4)), "1st of month matches");
+        assert!(c.matches(&at(0, 0, 9, 1, 1)), "Monday matches");
+        assert!(!c.matches(&at(0, 0, 9, 1, 2)));
+    }
+
+    #[test]
+    fn test_cron_stepped_day_is_unrestricted() {
+        let c = Cron::parse("0 9 */2 * 1").unwrap();
+        assert!(c.matches(&at(0, 9, 3, 1, 1)), "odd day and Monday");
+        assert!(!c.matches(&at(0, 9, 2, 1, 1)), "even day");
+        assert!(!c.matches(&at(0, 9, 3, 1, 2)), "not Monday");
+    }
+
+    #[test]
+    fn test_cron_rejects_invalid() {
+        assert!(Cron::parse("* * * *").is_err());
+        assert!(Cron::parse("60 * * * *").is_err());
+        assert!(Cron::parse("*/0 * * * *").is_err());
+        assert!(Cron::parse("a * * * *").is_err());
+        assert!(Cron::parse("5-2 * * * *").is_err());
+    }
+
+    #[test]
+    fn test_record_keeps_last_n() {
+        let name = "test-record-keeps-last-n";
+        let done = |_: &str| true;
+        assert!(record(name, "b1", 2, done).is_empty());
+        assert!(record(name, "b2", 2, done).is_empty());
+        assert_eq!(record(name, "b3", 2, done), vec!["b1"]);
+        let history = HISTORY.lock().unwrap();
+        assert_eq!(history[name], vec!["b2", "b3"]);
+    }
+
+    #[test]
+    fn test_record_evicts_running_batches_once_done() {
+        let name = "test-record-evicts-running-once-done";
+        let done_except_b1 = |id: &str| id != "b1";
+        assert!(record(name, "b1", 1, done_except_b1).is_empty());
+        assert!(record(name, "b2", 1, done_except_b1).is_empty());
+        assert_eq!(HISTORY.lock().unwrap()[name], vec!["b1", "b2"]);
+        assert_eq!(record(name, "b3", 1, done_except_b1), vec!["b2"]);
+        assert_eq!(HISTORY.lock().unwrap()[name], vec!["b1", "b3"]);
+        assert_eq!(record(name, "b4", 1, |_| true), vec!["b1", "b3"]);
+        assert_eq!(HISTORY.lock().unwrap()[name], vec!["b4"]);
+    }
+}
@@ -0,0 +1,533 @@
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
+
+use crate::kv::KvMap;
+use crate::project::{Project, ProjectKey};
+
+/*
+    A small query language over project attributes, e.g.
+
+        pr.state=OPEN and kv.task_type!=review
+        repo=temporal* and (dirty or jira.status="In Progress")
+        not kv.land-in
+
+    - `field=glob` / `field!=glob` match case-insensitively against a glob.
+    - `field~regex` / `field!~regex` match against a regex.
+    - A bare field is true if the attribute is present and not "false".
+    - A missing attribute never matches `=` or `~`, and always matches `!=` / `!~`.
+    - `and` binds tighter than `or`; `not` and parentheses work as usual.
+*/
+
+const FIELDS: &str =
+    "repo, branch, key, kv.<name>, jira.key, jira.status, pr.state, pr.number, pr.draft, task, active, dirty, clean";
+
+#[derive(Debug)]
+pub enum Selector {
+    And(Box<Selector>, Box<Selector>),
+    Or(Box<Selector>, Box<Selector>),
+    Not(Box<Selector>),
+    Test(Field, Option<Matcher>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Repo,
+    Branch,
+    Key,
+    Kv(String),
+    JiraKey,
+    JiraStatus,
+    PrState,
+    PrNumber,
+    PrDraft,
+    Task,
+    Active,
+    Dirty,
+    Clean,
+}
+
+#[derive(Debug)]
+pub enum Matcher {
+    Glob { pattern: Pattern, negate: bool },
+    Regex { regex: Regex, negate: bool },
+}
+
+/// State that is expensive to compute per project, fetched once per query.
+pub struct Context {
+    pub window_names: Vec<String>,
+}
+
+impl Selector {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let selector = parser.parse_or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(selector),
+            Some(t) => Err(format!("unexpected {:?} in '{}'", t, input)),
+        }
+    }
+
+    /// True if any predicate in this selector tests `field`.
+    pub fn uses_field(&self, field: &Field) -> bool {
+        match self {
+            Selector::And(a, b) | Selector::Or(a, b) => a.uses_field(field) || b.uses_field(field),
+            Selector::Not(s) => s.uses_field(field),
+            Selector::Test(f, _) => f == field,
+        }
+    }
+
+    pub fn matches(&self, project: &Project, ctx: &Context) -> bool {
+        self.eval(&|field| field.value(project, ctx))
+    }
+
+    /// Match a project known only by its key and stored KV, e.g. read from disk
+    /// for a task that isn't open. Only the fields accepted by
+    /// `check_stored_fields` have values.
+    pub fn matches_stored(&self, key: &ProjectKey, kv: &KvMap) -> bool {
+        self.eval(&|field| match field {
+            Field::Repo => Some(key.repo.to_string()),
+            Field::Branch => key.branch.as_ref().map(|b| b.to_string()),
+            Field::Key => Some(key.to_string()),
+            Field::Kv(k) => kv.get(k).map(crate::kv::value_to_string),
+            Field::Task => Some(key.branch.is_some().to_string()),
+            _ => None,
+        })
+    }
+
+    /// Error unless every field is one `matches_stored` can evaluate.
+    pub fn check_stored_fields(&self) -> Result<(), String> {
+        match self {
+            Selector::And(a, b) | Selector::Or(a, b) => {
+                a.check_stored_fields().and(b.check_stored_fields())
+            }
+            Selector::Not(s) => s.check_stored_fields(),
+            Selector::Test(
+                Field::Repo | Field::Branch | Field::Key | Field::Kv(_) | Field::Task,
+                _,
+            ) => Ok(()),
+            Selector::Test(field, _) => Err(format!(
+                "'{}' is not available here (use repo, branch, key, task or kv.<name>)",
+                field.name()
+            )),
+        }
+    }
+
+    fn eval(&self, value: &dyn Fn(&Field) -> Option<String>) -> bool {
+        match self {
+            Selector::And(a, b) => a.eval(value) && b.eval(value),
+            Selector::Or(a, b) => a.eval(value) || b.eval(value),
+            Selector::Not(s) => !s.eval(value),
+            Selector::Test(field, matcher) => {
+                let value = value(field);
+                match matcher {
+                    None => value.is_some_and(|v| !v.is_empty() && v != "false"),
+                    Some(m) => m.matches(value.as_deref()),
+                }
+            }
+        }
+    }
+}
+
+/// A `wormhole kv find` condition (`key=glob`, `key!=glob`, `key~regex`,
+/// `key!~regex` or a bare `key`) as a selector expression over `kv.<key>`.
+pub fn kv_condition(condition: &str) -> Result<String, String> {
+    let Some(i) = condition.find(['=', '~', '!']) else {
+        return Ok(format!("kv.{}", condition));
+    };
+    let (key, rest) = condition.split_at(i);
+    let op_len = if rest.starts_with('!') { 2 } else { 1 };
+    let (op, value) = rest.split_at(op_len.min(rest.len()));
+    if key.is_empty() || !matches!(op, "=" | "~" | "!=" | "!~") {
+        return Err(format!(
+            "invalid condition '{}': expected key=value, key~regex, key!=value or key!~regex",
+            condition
+        ));
+    }
+    let quote = if value.contains('"') { '\'' } else { '"' };
+    if value.contains(quote) {
+        return Err(format!(
+            "invalid condition '{}': value contains both quote characters",
+            condition
+        ));
+    }
+    Ok(format!("kv.{}{}{}{}{}", key, op, quote, value, quote))
+}
+
+impl Field {
+    fn parse(name: &str) -> Result<Self, String> {
+        if let Some(key) = name.strip_prefix("kv.") {
+            return Ok(Field::Kv(key.to_string()));
+        }
+        Ok(match name {
+            "repo" => Field::Repo,
+            "branch" => Field::Branch,
+            "key" => Field::Key,
+            "jira.key" => Field::JiraKey,
+            "jira.status" => Field::JiraStatus,
+            "pr.state" => Field::PrState,
+            "pr.number" => Field::PrNumber,
+            "pr.draft" => Field::PrDraft,
+            "task" => Field::Task,
+            "active" => Field::Active,
+            "dirty" => Field::Dirty,
+            "clean" => Field::Clean,
+            _ => {
+                return Err(format!(
+                    "unknown field '{}' (expected one of: {})",
+                    name, FIELDS
+                ))
+            }
+        })
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Field::Repo => "repo",
+            Field::Branch => "branch",
+            Field::Key => "key",
+            Field::Kv(k) => return format!("kv.{}", k),
+            Field::JiraKey => "jira.key",
+            Field::JiraStatus => "jira.status",
+            Field::PrState => "pr.state",
+            Field::PrNumber => "pr.number",
+            Field::PrDraft => "pr.draft",
+            Field::Task => "task",
+            Field::Active => "active",
+            Field::Dirty => "dirty",
+            Field::Clean => "clean",
+        }
+        .to_string()
+    }
+
+    fn value(&self, project: &Project, ctx: &Context) -> Option<String> {
+        let bool_str = |b: bool| Some(b.to_string());
+        match self {
+            Field::Repo => Some(project.repo_name.to_string()),
+            Field::Branch => project.branch.as_ref().map(|b| b.to_string()),
+            Field::Key => Some(project.store_key().to_string()),
+            Field::Kv(k) => {
+                crate::kv::scope::resolve(project, k).map(|r| crate::kv::value_to_string(&r.value))
+            }
+            Field::JiraKey => project.cached.jira.as_ref().map(|j| j.key.clone()),
+            Field::JiraStatus => project.cached.jira.as_ref().map(|j| j.status.clone()),
+            Field::PrState => project.cached.pr.as_ref().map(|p| p.state.clone()),
+            Field::PrNumber => project.cached.pr.as_ref().map(|p| p.number.to_string()),
+            Field::PrDraft => project.cached.pr.as_ref().map(|p| p.is_draft.to_string()),
+            Field::Task => bool_str(project.is_task()),
+            Field::Active => bool_str(project.is_active(&ctx.window_names)),
+            Field::Dirty => bool_str(!crate::git::is_working_tree_clean(&project.working_tree())),
+            Field::Clean => bool_str(crate::git::is_working_tree_clean(&project.working_tree())),
+        }
+    }
+}
+
+impl Matcher {
+    fn matches(&self, value: Option<&str>) -> bool {
+        let opts = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::new()
+        };
+        match self {
+            Matcher::Glob { pattern, negate } => {
+                value.is_some_and(|v| pattern.matches_with(v, opts)) != *negate
+            }
+            Matcher::Regex { regex, negate } => value.is_some_and(|v| regex.is_match(v)) != *negate,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '=' => {
+                chars.next();
+                tokens.push(Token::Op("="));
+            }
+            '~' => {
+                chars.next();
+                tokens.push(Token::Op("~"));
+            }
+            '!' => {
+                chars.next();
+                match chars.next() {
+                    Some('=') => tokens.push(Token::Op("!=")),
+                    Some('~') => tokens.push(Token::Op("!~")),
+                    _ => return Err("expected '!=' or '!~'".to_string()),
+                }
+            }
+            '"' | '\'' => {
+                let quote = c;
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some(ch) if ch == quote => break,
+                        Some(ch) => s.push(ch),
+                        None => return Err(format!("unterminated {} quote", quote)),
+                    }
+                }
+                tokens.push(Token::Quoted(s));
+            }
+            _ => {
+                let mut s = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()=~!\"'".contains(ch) {
+                        break;
+                    }
+                    s.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(s));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Selector, String> {
+        let mut left = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Selector::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Selector, String> {
+        let mut left = self.parse_unary()?;
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Selector::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Selector, String> {
+        if self.peek_keyword("not") {
+            self.pos += 1;
+            return Ok(Selector::Not(Box::new(self.parse_unary()?)));
+        }
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let inner = self.parse_or()?;
+                if self.tokens.get(self.pos) != Some(&Token::RParen) {
+                    return Err("expected ')'".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(Token::Word(name)) => {
+                self.pos += 1;
+                let field = Field::parse(&name)?;
+                let Some(Token::Op(op)) = self.tokens.get(self.pos).cloned() else {
+                    return Ok(Selector::Test(field, None));
+                };
+                self.pos += 1;
+                let value = match self.tokens.get(self.pos).cloned() {
+                    Some(Token::Word(v)) | Some(Token::Quoted(v)) => v,
+                    _ => return Err(format!("expected a value after '{}{}'", name, op)),
+                };
+                self.pos += 1;
+                Ok(Selector::Test(field, Some(matcher(op, &value)?)))
+            }
+            Some(t) => Err(format!("unexpected {:?}", t)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn matcher(op: &str, value: &str) -> Result<Matcher, String> {
+    let negate = op.starts_with('!');
+    if op.ends_with('~') {
+        let regex = Regex::new(value).map_err(|e| format!("invalid regex '{}': {}", value, e))?;
+        Ok(Matcher::Regex { regex, negate })
+    } else {
+        let pattern =
+            Pattern::new(value).map_err(|e| format!("invalid glob '{}': {}", value, e))?;
+        Ok(Matcher::Glob { pattern, negate })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CanonicalName;
+    use crate::project::{BranchName, Cached};
+    use std::collections::HashMap;
+
+    fn task(repo: &str, branch: &str, kv: &[(&str, &str)], pr_state: Option<&str>) -> Project {
+        Project {
+            repo_name: CanonicalName::for_test(repo),
+            repo_path: "/nonexistent".into(),
+            branch: Some(BranchName::new(branch)),
+            kv: kv
+                .iter()
+                .map(|(k, v)| (k.to_string(), serde_json::Value::from(*v)))
+                .collect::<HashMap<_, _>>(),
+            cached: Cached {
+                pr: pr_state.map(|state| crate::github::PrStatus {
+                    number: 1,
+                    state: state.to_string(),
+                    is_draft: false,
+                    url: String::new(),
+                    comments: vec![],
+                    base: None,
+                    checks: None,
+                    review_decision: None,
+                    requested_reviewers: vec![],
+                    unlisted_reviewers: 0,
+                    mergeable: None,
+                }),
+                ..Cached::default()
+            },
+        }
+    }
+
+    fn matches(expr: &str, project: &Project) -> bool {
+        let ctx = Context {
+            window_names: vec!["temporal:active-task".to_string()],
+        };
+        Selector::parse(expr).unwrap().matches(project, &ctx)
+    }
+
+    #[test]
+    fn test_kv_and_pr_predicates() {
+        let review = task("cli", "rev", &[("task_type", "review")], Some("OPEN"));
+        let feature = task("cli", "feat", &[], Some("OPEN"));
+        let merged = task("cli", "old", &[], Some("MERGED"));
+        let expr = "pr.state=OPEN and kv.task_type!=review";
+        assert!(!matches(expr, &review));
+        assert!(matches(expr, &feature));
+        assert!(!matches(expr, &merged));
+        assert!(matches("pr.state=open", &feature), "case-insensitive");
+    }
+
+    #[test]
+    fn test_kv_predicates_see_inherited_values() {
+        crate::kv::scope::set_repo_for_test(
+            "where-scope-test-repo",
+            crate::kv::KvMap::from([("agent".to_string(), serde_json::json!("codex"))]),
+        );
+        let inherits = task("where-scope-test-repo", "feat", &[], None);
+        let overrides = task(
+            "where-scope-test-repo",
+            "other",
+            &[("agent", "aider")],
+            None,
+        );
+        assert!(matches("kv.agent=codex", &inherits));
+        assert!(!matches("kv.agent=codex", &overrides));
+        assert!(matches("kv.agent=aider", &overrides));
+    }
+
+    #[test]
+    fn test_repo_glob_and_regex() {
+        let t = task("temporal-ui", "x", &[], None);
+        assert!(matches("repo=temporal*", &t));
+        assert!(!matches("repo=cli", &t));
+        assert!(matches("repo~^temp.*ui$", &t));
+        assert!(matches("repo!~^cli", &t));
+    }
+
+    #[test]
+    fn test_bare_fields_and_missing_values() {
+        let t = task("temporal", "active-task", &[("land-in", "editor")], None);
+        assert!(matches("task and active", &t));
+        assert!(matches("kv.land-in", &t));
+        assert!(!matches("kv.missing", &t));
+        assert!(matches("not kv.missing", &t));
+        assert!(!matches("pr.state=OPEN", &t), "missing never matches =");
+        assert!(matches("pr.state!=OPEN", &t), "missing always matches !=");
+    }
+
+    #[test]
+    fn test_precedence_and_parens() {
+        let t = task("cli", "feat", &[], None);
+        // and binds tighter: false or (true and true)
+        assert!(matches("repo=nope or task and repo=cli", &t));
+        assert!(!matches("(repo=nope or task) and repo=other", &t));
+        assert!(matches("branch=\"feat\"", &t));
+    }
+
+    #[test]
+    fn test_matches_stored_kv() {
+        let key = ProjectKey::task("cli", "feat");
+        let kv = KvMap::from([("status".to_string(), serde_json::json!("in-progress"))]);
+        let stored = |expr: &str| {
+            let s = Selector::parse(expr).unwrap();
+            s.check_stored_fields().unwrap();
+            s.matches_stored(&key, &kv)
+        };
+        assert!(stored("kv.status=in-* and repo=cli and task"));
+        assert!(!stored("kv.land-in"));
+        assert!(Selector::parse("dirty")
+            .unwrap()
+            .check_stored_fields()
+            .is_err());
+    }
+
+    #[test]
+    fn test_kv_condition() {
+        assert_eq!(
+            kv_condition("land-in=editor").unwrap(),
+            r#"kv.land-in="editor""#
+        );
+        assert_eq!(kv_condition("status!~^in").unwrap(), r#"kv.status!~"^in""#);
+        assert_eq!(
+            kv_condition("note=say \"hi\"").unwrap(),
+            r#"kv.note='say "hi"'"#
+        );
+        assert_eq!(kv_condition("jira_key").unwrap(), "kv.jira_key");
+        assert!(kv_condition("=x").is_err());
+        assert!(kv_condition("a!b").is_err());
+        let s = Selector::parse(&kv_condition("aux-repos~cli").unwrap()).unwrap();
+        let kv = KvMap::from([("aux-repos".to_string(), serde_json::json!(["cli"]))]);
+        assert!(s.matches_stored(&ProjectKey::project("x"), &kv));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Selector::parse("bogus=1").is_err());
+        assert!(Selector::parse("repo=").is_err());
+        assert!(Selector::parse("(repo=a").is_err());
+        assert!(Selector::parse("repo=a and").is_err());
+        assert!(Selector::parse("repo~(").is_err());
+        assert!(Selector::parse("repo=a repo=b").is_err());
+    }
+}
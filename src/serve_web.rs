@@ -147,7 +147,7 @@ mod tests {
         let mgr = ServeWebManager::new(18000);
         for task in ["ACT-1", "ACT-999", "LONG-TASK-NAME-12345"] {
             let port = mgr.port_for_task(task);
-            assert!(port >= 18000 && port < 19000, "port {} out of range", port);
+            assert!((18000..19000).contains(&port), "port {} out of range", port);
         }
     }
 }
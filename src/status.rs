@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use serde::{Deserialize, Serialize};
 
 use crate::github::PrStatus;
@@ -23,7 +21,6 @@ pub fn get_status(project: &Project) -> TaskStatus {
     let name = project.repo_name.to_string();
     let branch = project.branch.as_ref().map(|b| b.to_string());
     let path = project.working_tree();
-    let kv = project.kv.clone();
 
     let claude_md_exists = path.join("CLAUDE.md").exists();
     let claude_md_url = if claude_md_exists {
@@ -31,7 +28,8 @@ pub fn get_status(project: &Project) -> TaskStatus {
     } else {
         None
     };
-    let aux_repos = kv.get("aux-repos").cloned();
+    let aux_repos = crate::kv::scope::resolve(project, "aux-repos")
+        .map(|r| crate::kv::value_to_string(&r.value));
 
     TaskStatus {
         name,
@@ -51,7 +49,10 @@ pub struct ProjectInfo {
     pub path: std::path::PathBuf,
     pub branch: Option<String>,
     pub active: bool,
-    pub kv: HashMap<String, String>,
+    pub kv: crate::kv::KvMap,
+    /// Synced agent sessions, newest first
+    #[serde(default)]
+    pub conversations: Vec<crate::conversations::timeline::SessionSummary>,
 }
 
 pub fn get_info(project: &Project) -> ProjectInfo {
@@ -62,21 +63,25 @@ pub fn get_info(project: &Project) -> ProjectInfo {
         branch: project.branch.as_ref().map(|b| b.to_string()),
         active: project.is_active(&window_names),
         kv: project.kv.clone(),
+        conversations: crate::conversations::timeline::task_sessions(
+            &project.store_key().to_string(),
+        ),
     }
 }
 
 pub fn get_info_by_name(name: &str) -> Option<ProjectInfo> {
-    let projects = projects::lock();
-    let key = ProjectKey::parse(name);
-    let project = projects
-        .by_key(&key)
-        .or_else(|| projects.by_path(std::path::Path::new(name)))?;
+    let project = {
+        let projects = projects::lock();
+        let key = ProjectKey::parse(name);
+        projects
+            .by_key(&key)
+            .or_else(|| projects.by_path(std::path::Path::new(name)))?
+    };
     Some(get_info(&project))
 }
 
 pub fn get_current_info() -> Option<ProjectInfo> {
-    let projects = projects::lock();
-    let project = projects.current()?;
+    let project = projects::lock().current()?;
     Some(get_info(&project))
 }
 
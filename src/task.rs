@@ -38,13 +38,14 @@ pub fn create_task(repo: &str, branch: &str) -> Result<Project, String> {
         setup_task_worktree(&worktree_path, repo, branch)?;
     }
 
-    let task = Project {
+    let mut task = Project {
         repo_name: config::canonical_project_name(&repo_path),
         repo_path,
         branch: Some(crate::project::BranchName::new(branch)),
         kv: std::collections::HashMap::new(),
         cached: crate::project::Cached::default(),
     };
+    crate::kv::load_project_kv(&mut task);
 
     {
         let mut projects = projects::lock();
@@ -89,7 +90,7 @@ pub fn open_task(repo: &str, branch: &str, land_in: Option<LandIn>) -> Result<()
 
     // Editor is the opt-in upper row of the grid; default to a pure tmux spawn.
     let land_in = land_in
-        .or_else(|| crate::wormhole::parse_land_in(project.kv.get("land-in")))
+        .or_else(|| crate::wormhole::parse_land_in(project.kv_resolved_str("land-in").as_deref()))
         .or(Some(LandIn::TerminalOnly));
     match land_in {
         Some(LandIn::TerminalOnly) => {
@@ -197,9 +198,9 @@ pub fn create_review_tasks(dry_run: bool) -> Result<ReviewTaskResult, String> {
                     continue;
                 }
                 let key = ProjectKey::task(home.as_str(), &branch);
-                crate::kv::set_value_sync(&key, "task_type", "review");
-                crate::kv::set_value_sync(&key, "review_pr_url", &pr.url);
-                crate::kv::set_value_sync(&key, "review_pr_title", &pr.title);
+                crate::kv::set_value_sync(&key, "task", "task_type", "review");
+                crate::kv::set_value_sync(&key, "task", "review_pr_url", &pr.url);
+                crate::kv::set_value_sync(&key, "task", "review_pr_title", &pr.title);
                 if already_exists {
                     result.skipped.push(format!("{} (updated)", task_key));
                 } else {
@@ -301,21 +302,33 @@ pub fn create_github_ref_task(
     match kind {
         GithubRefKind::Pr => {
             crate::github::pr_checkout(&task.working_tree(), &r.owner, &r.repo, r.number)?;
-            crate::kv::set_value_sync(&key, "task_type", "review");
+            crate::kv::set_value_sync(&key, "task", "task_type", "review");
             crate::kv::set_value_sync(
                 &key,
+                "task",
                 "review_pr_url",
-                &format!("https://github.com/{}/pull/{}", nwo, r.number),
+                &format!(
+                    "https://{}/{}/pull/{}",
+                    crate::config::github_host(),
+                    nwo,
+                    r.number
+                ),
             );
         }
         GithubRefKind::Issue => {
-            crate::kv::set_value_sync(&key, "task_type", "issue");
+            crate::kv::set_value_sync(&key, "task", "task_type", "issue");
             crate::kv::set_value_sync(
                 &key,
+                "task",
                 "github_issue_url",
-                &format!("https://github.com/{}/issues/{}", nwo, r.number),
+                &format!(
+                    "https://{}/{}/issues/{}",
+                    crate::config::github_host(),
+                    nwo,
+                    r.number
+                ),
             );
-            crate::kv::set_value_sync(&key, "github_issue_number", &r.number.to_string());
+            crate::kv::set_value_sync(&key, "task", "github_issue_number", &r.number.to_string());
         }
     }
     projects::refresh_cache();
@@ -328,7 +341,7 @@ pub fn create_github_ref_task(
     })
 }
 
-fn build_github_repo_map() -> std::collections::HashMap<String, config::CanonicalName> {
+pub fn build_github_repo_map() -> std::collections::HashMap<String, config::CanonicalName> {
     let mut map = std::collections::HashMap::new();
     for (name, path) in config::available_projects() {
         if let Some(github_repo) = git::github_repo_from_remote(&path) {
@@ -1,4 +1,5 @@
 use regex::Regex;
+use std::collections::BTreeMap;
 
 use crate::{git, hammerspoon, project::Project, tmux, wezterm};
 
@@ -106,6 +107,65 @@ pub fn shell_env_code(project: &Project) -> String {
     )
 }
 
+/// Environment for a batch run in `project`: the variables set in its tmux
+/// window, plus each KV entry, inherited ones included, as `WORMHOLE_KV_<KEY>`.
+/// Keys whose names collide (e.g. `land-in` and `land_in`) are left out, with
+/// a warning, rather than one of them silently winning.
+pub fn batch_env_vars(project: &Project) -> Vec<(String, String)> {
+    let vars = shell_env_vars(project);
+    let mut env = vec![
+        ("WORMHOLE_PROJECT_NAME".to_string(), vars.project_name),
+        ("WORMHOLE_PROJECT_DIR".to_string(), vars.project_dir),
+        ("WORMHOLE_JIRA_URL".to_string(), vars.jira_url),
+        ("WORMHOLE_GITHUB_REPO".to_string(), vars.github_repo),
+        ("WORMHOLE_GITHUB_PR_URL".to_string(), vars.github_pr_url),
+    ];
+    env.extend(kv_env_vars(&crate::kv::scope::resolved_kv(project)));
+    env
+}
+
+/// `WORMHOLE_KV_<KEY>` variables for `kv`, sorted, without keys whose names
+/// collide.
+fn kv_env_vars(kv: &crate::kv::KvMap) -> Vec<(String, String)> {
+    let mut by_name: BTreeMap<String, Vec<(&String, &serde_json::Value)>> = BTreeMap::new();
+    for (key, value) in kv {
+        by_name
+            .entry(kv_env_name(key))
+            .or_default()
+            .push((key, value));
+    }
+    let mut env = Vec::new();
+    for (name, mut entries) in by_name {
+        if let [(_, value)] = entries.as_slice() {
+            env.push((name, crate::kv::value_to_string(value)));
+        } else {
+            entries.sort_by_key(|(k, _)| *k);
+            let keys: Vec<&str> = entries.iter().map(|(k, _)| k.as_str()).collect();
+            eprintln!(
+                "Not setting {}: KV keys {} all map to it",
+                name,
+                keys.join(", ")
+            );
+        }
+    }
+    env
+}
+
+/// `land-in` -> `WORMHOLE_KV_LAND_IN`
+fn kv_env_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("WORMHOLE_KV_{}", name)
+}
+
 fn jira_url_for_name(name: &str) -> Option<String> {
     let jira_key_re = Regex::new(r"^([A-Z]+-\d+)").ok()?;
     let key = jira_key_re.captures(name)?.get(1)?.as_str();
@@ -117,6 +177,47 @@ fn jira_url_for_name(name: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_kv_env_name() {
+        assert_eq!(kv_env_name("land-in"), "WORMHOLE_KV_LAND_IN");
+        assert_eq!(kv_env_name("task_type"), "WORMHOLE_KV_TASK_TYPE");
+        assert_eq!(kv_env_name("a.b c"), "WORMHOLE_KV_A_B_C");
+
+        use serde_json::json;
+        let kv = crate::kv::KvMap::from([
+            ("land-in".to_string(), json!("editor")),
+            ("land_in".to_string(), json!("terminal")),
+            ("foo.bar".to_string(), json!(1)),
+        ]);
+        assert_eq!(
+            kv_env_vars(&kv),
+            vec![("WORMHOLE_KV_FOO_BAR".to_string(), "1".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_batch_env_vars_include_inherited_kv() {
+        use serde_json::json;
+        crate::kv::scope::set_repo_for_test(
+            "env-scope-test-repo",
+            crate::kv::KvMap::from([
+                ("agent".to_string(), json!("codex")),
+                ("land-in".to_string(), json!("terminal")),
+            ]),
+        );
+        let project = Project {
+            repo_name: crate::config::CanonicalName::for_test("env-scope-test-repo"),
+            repo_path: "/nonexistent".into(),
+            branch: None,
+            kv: crate::kv::KvMap::from([("land-in".to_string(), json!("editor"))]),
+            cached: Default::default(),
+        };
+        let env = batch_env_vars(&project);
+        let var = |name: &str| env.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str());
+        assert_eq!(var("WORMHOLE_KV_AGENT"), Some("codex"));
+        assert_eq!(var("WORMHOLE_KV_LAND_IN"), Some("editor"));
+    }
+
     #[test]
     fn test_jira_url_for_name_with_suffix() {
         std::env::set_var("JIRA_INSTANCE", "testinst");
@@ -47,17 +47,22 @@ pub fn project_directories() -> Vec<String> {
         .collect()
 }
 
+/// Names of the tmux windows; empty if tmux isn't running.
 pub fn window_names() -> Vec<String> {
-    list_windows().into_iter().map(|w| w.name).collect()
+    list_windows()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|w| w.name)
+        .collect()
 }
 
 pub fn exists(project: &Project) -> bool {
-    get_window(&project.store_key().to_string()).is_some()
+    get_window(&project.store_key().to_string()).is_ok_and(|w| w.is_some())
 }
 
 pub fn open(project: &Project) -> Result<(), String> {
     let window_name = project.store_key().to_string();
-    if let Some(window) = get_window(&window_name) {
+    if let Some(window) = get_window(&window_name)? {
         tmux(["select-window", "-t", &window.id]);
     } else {
         let vars = shell_env_vars(project);
@@ -130,14 +135,13 @@ fn project_window_ids(store_key: &str) -> Vec<String> {
     ids
 }
 
-/// Open (or focus) a tmux pane running `claude -r <session_id>` in the project's
+/// Open (or focus) a tmux pane continuing a conversation in the project's
 /// window. A pane already running this session is reused; otherwise a new pane
-/// is split off, tagged with the session id, and `claude -r` is launched in it.
-pub fn resume_claude_session(project: &Project, session_id: &str) {
+/// is split off, tagged with the session id, and `command` is launched in it.
+pub fn resume_session(project: &Project, session_id: &str, command: &str) {
     let _ = open(project);
-    let window = match get_window(&project.store_key().to_string()) {
-        Some(w) => w,
-        None => return,
+    let Ok(Some(window)) = get_window(&project.store_key().to_string()) else {
+        return;
     };
     if let Some(pane_id) = find_session_pane(&window.id, session_id) {
         tmux(["select-window", "-t", &window.id]);
@@ -167,12 +171,76 @@ pub fn resume_claude_session(project: &Project, session_id: &str) {
         SESSION_PANE_OPTION,
         session_id,
     ]);
-    let cmd = format!("claude -r {session_id}");
-    tmux(["send-keys", "-t", pane_id, cmd.as_str(), "Enter"]);
+    tmux(["send-keys", "-t", pane_id, command, "Enter"]);
     tmux(["select-window", "-t", &window.id]);
     tmux(["select-pane", "-t", pane_id]);
 }
 
+/// Run `script` with `sh -c` as the process of a new, unfocused pane split
+/// off the window named `window_name`, with `env` set, returning the pane id.
+/// The pane is kept after the script exits so that `pane_exit_status` can
+/// tell that it has finished.
+pub fn spawn_pane(
+    window_name: &str,
+    dir: &str,
+    env: &[(String, String)],
+    script: &str,
+) -> Result<String, String> {
+    let window =
+        get_window(window_name)?.ok_or_else(|| format!("no tmux window for {}", window_name))?;
+    let mut args = vec![
+        "split-window".to_string(),
+        "-d".to_string(),
+        "-t".to_string(),
+        window.id.clone(),
+        "-c".to_string(),
+        dir.to_string(),
+        "-P".to_string(),
+        "-F".to_string(),
+        "#{pane_id}".to_string(),
+    ];
+    for (name, value) in env {
+        args.push("-e".to_string());
+        args.push(format!("{}={}", name, value));
+    }
+    args.extend(["--", "sh", "-c", script].map(String::from));
+    let pane_id = try_tmux_vec(args)?;
+    let pane_id = pane_id.trim().to_string();
+    try_tmux_vec(vec![
+        "set-option".to_string(),
+        "-p".to_string(),
+        "-t".to_string(),
+        pane_id.clone(),
+        "remain-on-exit".to_string(),
+        "on".to_string(),
+    ])?;
+    Ok(pane_id)
+}
+
+/// The exit status of a pane's process, or None while it is still running.
+/// Errors if the pane no longer exists.
+pub fn pane_exit_status(pane_id: &str) -> Result<Option<i32>, String> {
+    let out = try_tmux_vec(vec![
+        "display-message".to_string(),
+        "-p".to_string(),
+        "-t".to_string(),
+        pane_id.to_string(),
+        "#{pane_dead} #{pane_dead_status}".to_string(),
+    ])?;
+    match out.trim().split_once(' ') {
+        Some(("1", status)) => Ok(Some(status.parse().unwrap_or(-1))),
+        _ => Ok(None),
+    }
+}
+
+pub fn kill_pane(pane_id: &str) {
+    let _ = try_tmux_vec(vec![
+        "kill-pane".to_string(),
+        "-t".to_string(),
+        pane_id.to_string(),
+    ]);
+}
+
 const SESSION_PANE_OPTION: &str = "@wormhole_claude_session";
 
 fn find_session_pane(window_id: &str, session_id: &str) -> Option<String> {
@@ -185,21 +253,27 @@ fn find_session_pane(window_id: &str, session_id: &str) -> Option<String> {
         })
 }
 
-fn get_window(name: &str) -> Option<Window> {
-    list_windows().into_iter().find(|w| w.name == name)
+fn get_window(name: &str) -> Result<Option<Window>, String> {
+    Ok(list_windows()?.into_iter().find(|w| w.name == name))
 }
 
-fn list_windows() -> Vec<Window> {
-    tmux(["list-windows", "-F", "#I #W"])
+/// Errors if tmux is missing or its server isn't running.
+fn list_windows() -> Result<Vec<Window>, String> {
+    let out = try_tmux_vec(vec![
+        "list-windows".to_string(),
+        "-F".to_string(),
+        "#I #W".to_string(),
+    ])?;
+    Ok(out
         .split_terminator("\n")
-        .map(|line| {
-            let mut fields = line.split(" ");
-            Window {
-                id: fields.next().unwrap().to_string(),
-                name: fields.next().unwrap().to_string(),
-            }
+        .filter_map(|line| {
+            let (id, name) = line.split_once(' ')?;
+            Some(Window {
+                id: id.to_string(),
+                name: name.to_string(),
+            })
         })
-        .collect()
+        .collect())
 }
 
 pub fn tmux<'a, I>(args: I) -> String
@@ -210,9 +284,15 @@ where
 }
 
 fn tmux_vec(args: Vec<String>) -> String {
+    try_tmux_vec(args).unwrap_or_else(|e| panic(&e))
+}
+
+/// Like `tmux`, but returns an error instead of panicking. Used where the
+/// target may legitimately vanish, e.g. a batch pane closed by the user.
+fn try_tmux_vec(args: Vec<String>) -> Result<String, String> {
     let socket_path = std::env::var("WORMHOLE_TMUX")
         .or_else(|_| std::env::var("TMUX"))
-        .unwrap_or_else(|_| panic("TMUX env var is not set"))
+        .map_err(|_| "TMUX env var is not set".to_string())?
         .split(",")
         .next()
         .unwrap()
@@ -223,6 +303,6 @@ fn tmux_vec(args: Vec<String>) -> String {
         .args(["-S", &socket_path])
         .args(&args)
         .output()
-        .unwrap_or_else(|_| panic("Failed to execute command"));
-    get_stdout(program, output).unwrap_or_else(|e| panic(&e))
+        .map_err(|e| format!("Failed to execute {program}: {e}"))?;
+    get_stdout(program, output)
 }
@@ -46,8 +46,8 @@ impl From<Application> for LandIn {
     }
 }
 
-pub fn parse_land_in(s: Option<&String>) -> Option<LandIn> {
-    s.and_then(|v| match v.as_str() {
+pub fn parse_land_in(s: Option<&str>) -> Option<LandIn> {
+    s.and_then(|v| match v {
         "terminal" | "terminal-only" => Some(LandIn::TerminalOnly),
         "editor" => Some(LandIn::Editor),
         "none" => Some(LandIn::Background),
@@ -78,6 +78,19 @@ pub struct QueryParams {
     pub with_editor: bool,
     pub remove: bool,
     pub prune: bool,
+    pub selector: Option<String>,
+    pub pointer: Option<String>,
+    pub json: bool,
+    pub source: Option<String>,
+    pub resolved: bool,
+    pub overwrite: bool,
+    pub missing: Option<String>,
+    pub query: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+    pub tools: bool,
+    pub format: Option<String>,
+    pub redact: bool,
 }
 
 pub async fn service(req: Request<Body>) -> Result<Response<Body>, Infallible> {
@@ -122,7 +135,12 @@ async fn route(
         "/project/describe" => {
             require_post_async(method, || async { describe::describe(req).await }).await
         }
-        "/project/list" => project::list_projects(params.active, params.tasks, params.with_editor),
+        "/project/list" => project::list_projects(
+            params.active,
+            params.tasks,
+            params.with_editor,
+            params.selector.as_deref(),
+        ),
         "/project/neighbors" => project::neighbors(params.active),
         "/project/next" => {
             project::navigate(project::Direction::Next, params);
@@ -220,12 +238,29 @@ async fn route(
             Method::GET => batch::list_batches(),
             _ => method_not_allowed(),
         },
+        "/schedule" => batch::list_schedules(),
         "/" => dashboard::dashboard(),
         "/favicon.png" => handlers::favicon(),
         "/shell" => project::shell_env(params.pwd.as_deref()),
         "/kv" => crate::kv::list_all_kv_fresh(),
+        "/kv-export" => crate::kv::bundle::export_response(),
+        "/kv-find" => {
+            let missing: Vec<&str> = params
+                .missing
+                .as_deref()
+                .map(|m| m.split(',').map(|s| s.trim()).collect())
+                .unwrap_or_default();
+            crate::kv::find(params.selector.as_deref(), &missing)
+        }
+        "/kv-import" => {
+            let (overwrite, dry_run) = (params.overwrite, params.dry_run);
+            require_post_async(method, || async move {
+                crate::kv::bundle::import_request(req, overwrite, dry_run).await
+            })
+            .await
+        }
         "/conversations/sync" => require_post(method, || {
-            let projects = project_dirs_for_sync();
+            let projects = crate::conversations::project_dirs();
             let filter: Option<Vec<&str>> = params
                 .project
                 .as_deref()
@@ -240,12 +275,72 @@ async fn route(
                 since,
                 params.prune,
                 params.dry_run,
+                params.tools,
             );
             Response::builder()
                 .header("Content-Type", "application/json")
                 .body(Body::from(serde_json::to_string(&result).unwrap()))
                 .unwrap()
         }),
+        "/conversations/regenerate" => require_post(method, || {
+            let projects = crate::conversations::project_dirs();
+            let filter: Option<Vec<&str>> = params
+                .project
+                .as_deref()
+                .map(|p| p.split(',').map(|s| s.trim()).collect());
+            let result = crate::conversations::regenerate(&projects, filter.as_deref());
+            Response::builder()
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&result).unwrap()))
+                .unwrap()
+        }),
+        "/conversations/scan" => {
+            use crate::conversations::redact;
+            let scan = move || {
+                let leaks = redact::scan(
+                    &crate::conversations::conversations_dir(),
+                    &redact::Redactor::from_config(),
+                    params.redact,
+                );
+                Response::builder()
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(serde_json::to_string(&leaks).unwrap()))
+                    .unwrap()
+            };
+            if params.redact {
+                require_post(method, scan)
+            } else {
+                scan()
+            }
+        }
+        "/conversations/search" => {
+            use crate::conversations::index;
+            let bounds = (
+                params.since.as_deref().map(index::date_bound).transpose(),
+                params.until.as_deref().map(index::date_bound).transpose(),
+            );
+            let (since, until) = match bounds {
+                (Ok(since), Ok(until)) => (since, until),
+                (Err(e), _) | (_, Err(e)) => {
+                    return Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::from(e))
+                        .unwrap()
+                }
+            };
+            let query = index::SearchQuery {
+                text: params.query.clone().unwrap_or_default(),
+                project: params.project.clone(),
+                since,
+                until,
+                limit: params.limit.unwrap_or(20),
+            };
+            let hits = index::lock().search(&crate::conversations::conversations_dir(), &query);
+            Response::builder()
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&hits).unwrap()))
+                .unwrap()
+        }
         _ => route_with_params(req, method, path, params).await,
     }
 }
@@ -263,8 +358,18 @@ async fn route_with_params(
         if let Some(id) = rest.strip_suffix("/output") {
             return cors_response(batch::batch_output(id, params.run, params.offset));
         }
+        if let Some(id) = rest.strip_suffix("/report") {
+            return cors_response(batch::batch_report(id, params.format.as_deref()));
+        }
         return cors_response(batch::batch_status(rest, &req, params.completed).await);
     }
+    if let Some(name) = path
+        .strip_prefix("/schedule/")
+        .and_then(|rest| rest.strip_suffix("/run"))
+    {
+        let name = percent_encoding::percent_decode_str(name).decode_utf8_lossy();
+        return require_post(method, || batch::run_schedule(&name));
+    }
     if let Some(name) = path.strip_prefix("/project/messages/") {
         return match *method {
             Method::GET => {
@@ -304,8 +409,13 @@ async fn route_with_params(
     if let Some(name) = path.strip_prefix("/project/vscode/") {
         return cors_response(project::vscode_url(name));
     }
+    if let Some(session) = path.strip_prefix("/conversations/export/") {
+        let session = percent_encoding::percent_decode_str(session).decode_utf8_lossy();
+        return crate::conversations::export::export_response(&session, params.format.as_deref());
+    }
     if let Some(file_path) = path.strip_prefix("/conversations/resume") {
         let file_path = file_path.strip_prefix('/').unwrap_or(file_path);
+        let file_path = percent_encoding::percent_decode_str(file_path).decode_utf8_lossy();
         return require_post(method, || {
             handle_conversation_resume(&format!("/{}", file_path))
         });
@@ -318,12 +428,37 @@ async fn route_with_params(
     }
     if let Some(kv_key) = path.strip_prefix("/kv-all/") {
         if *method == Method::DELETE {
-            return crate::kv::delete_value_all(kv_key);
+            return crate::kv::delete_value_all(params.source.as_deref().unwrap_or("http"), kv_key);
         }
         return method_not_allowed();
     }
+    if let Some(kv_path) = path.strip_prefix("/kv-history/") {
+        if *method != Method::GET {
+            return method_not_allowed();
+        }
+        let (target, kv_key) = parse_kv_path(kv_path);
+        return match crate::kv::scope::ScopeTarget::parse(&target) {
+            Some(scope) => crate::kv::history::get_scope_history(&scope, kv_key.as_deref()),
+            None => {
+                let key = crate::project::ProjectKey::parse(&target);
+                crate::kv::history::get_history(&key, kv_key.as_deref())
+            }
+        };
+    }
+    if let Some(kv_path) = path.strip_prefix("/kv-undo/") {
+        let (target, kv_key) = parse_kv_path(kv_path);
+        return require_post(method, || {
+            match crate::kv::scope::ScopeTarget::parse(&target) {
+                Some(scope) => crate::kv::history::undo_scope(&scope, kv_key.as_deref()),
+                None => {
+                    let key = crate::project::ProjectKey::parse(&target);
+                    crate::kv::history::undo(&key, kv_key.as_deref())
+                }
+            }
+        });
+    }
     if let Some(kv_path) = path.strip_prefix("/kv/") {
-        return handle_kv_request(method, kv_path, req).await;
+        return handle_kv_request(method, kv_path, params, req).await;
     }
 
     route_file_or_github(path, params)
@@ -424,7 +559,27 @@ where
     }
 }
 
-async fn handle_kv_request(method: &Method, kv_path: &str, req: Request<Body>) -> Response<Body> {
+/// Split `<project>[/<key>]` into a decoded target (a project, or a scope
+/// such as `@global`) and optional KV key.
+fn parse_kv_path(kv_path: &str) -> (String, Option<String>) {
+    let decode = |s: &str| {
+        percent_encoding::percent_decode_str(s)
+            .decode_utf8_lossy()
+            .into_owned()
+    };
+    let (project, kv_key) = match kv_path.split_once('/') {
+        Some((p, k)) => (p, Some(decode(k))),
+        None => (kv_path, None),
+    };
+    (decode(project), kv_key)
+}
+
+async fn handle_kv_request(
+    method: &Method,
+    kv_path: &str,
+    params: &QueryParams,
+    req: Request<Body>,
+) -> Response<Body> {
     use crate::kv;
     use crate::project::ProjectKey;
 
@@ -438,6 +593,28 @@ async fn handle_kv_request(method: &Method, kv_path: &str, req: Request<Body>) -
         .collect();
     let parts: Vec<&str> = decoded.iter().map(|s| s.as_str()).collect();
 
+    if let Some(target) = parts.first().and_then(|p| kv::scope::ScopeTarget::parse(p)) {
+        let pointer = params.pointer.as_deref();
+        let source = params.source.as_deref().unwrap_or("http");
+        return match (parts.as_slice(), method.clone()) {
+            ([_], Method::GET) => kv::scope::get_scope_kv(&target),
+            ([_, kv_key], Method::GET) => {
+                kv::scope::get_value(&target, kv_key, pointer, params.json)
+            }
+            ([_, kv_key], Method::PUT) => {
+                let (_, body) = req.into_parts();
+                kv::scope::set_value(&target, source, kv_key, pointer, params.json, body).await
+            }
+            ([_, kv_key], Method::DELETE) => {
+                kv::scope::delete_value(&target, source, kv_key, pointer)
+            }
+            _ => Response::builder()
+                .status(StatusCode::METHOD_NOT_ALLOWED)
+                .body(Body::from("Method not allowed"))
+                .unwrap(),
+        };
+    }
+
     match parts.as_slice() {
         [""] => kv::list_all_kv_fresh(),
         [project] => {
@@ -453,8 +630,11 @@ async fn handle_kv_request(method: &Method, kv_path: &str, req: Request<Body>) -
         }
         [project, kv_key] => {
             let key = ProjectKey::parse(project);
+            let pointer = params.pointer.as_deref();
+            let source = params.source.as_deref().unwrap_or("http");
             match *method {
-                Method::GET => kv::get_value(&key, kv_key),
+                Method::GET if params.resolved => kv::scope::get_resolved(&key, kv_key),
+                Method::GET => kv::get_value(&key, kv_key, pointer, params.json),
                 Method::PUT => {
                     if *kv_key == "status" {
                         let projects = crate::projects::lock();
@@ -471,9 +651,9 @@ async fn handle_kv_request(method: &Method, kv_path: &str, req: Request<Body>) -
                         drop(projects);
                     }
                     let (_, body) = req.into_parts();
-                    kv::set_value(&key, kv_key, body).await
+                    kv::set_value(&key, source, kv_key, pointer, params.json, body).await
                 }
-                Method::DELETE => kv::delete_value(&key, kv_key),
+                Method::DELETE => kv::delete_value(&key, source, kv_key, pointer),
                 _ => Response::builder()
                     .status(StatusCode::METHOD_NOT_ALLOWED)
                     .body(Body::from("Method not allowed. Use GET, PUT, or DELETE"))
@@ -488,82 +668,65 @@ async fn handle_kv_request(method: &Method, kv_path: &str, req: Request<Body>) -
 }
 
 fn handle_conversation_resume(synced_file_path: &str) -> Response<Body> {
-    let synced_file = std::path::Path::new(synced_file_path);
-
-    // Parse the header to get project key and CC session UUID
-    let (project_key_str, session_id_str) =
-        match crate::conversations::parse_conversation_header(synced_file) {
-            Some(result) => result,
-            None => {
-                return Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(Body::from("Cannot parse conversation header"))
-                    .unwrap();
-            }
-        };
-
-    let project_key = crate::project::ProjectKey::parse(&project_key_str);
+    use crate::conversations::resume;
 
-    // Verify project exists
-    {
-        let store = projects::lock();
-        if store.by_key(&project_key).is_none() {
+    let synced_file = std::path::PathBuf::from(synced_file_path);
+    let Some(header) = crate::conversations::Header::read(&synced_file) else {
+        return Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::from("Cannot parse conversation header"))
+            .unwrap();
+    };
+
+    let project_key = crate::project::ProjectKey::parse(&header.project);
+    let Some(project) = projects::lock().by_key(&project_key) else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from(format!("Project not found: {}", project_key)))
+            .unwrap();
+    };
+
+    // Continue in the agent the project is configured for
+    let session = resume::Session {
+        header,
+        synced_file,
+    };
+    let prepared = resume::target(project.kv_resolved_str("agent").as_deref())
+        .and_then(|target| Ok((target.name(), target.prepare(&session)?)));
+    let (agent, command) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
             return Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(Body::from(format!("Project not found: {}", project_key)))
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(e))
                 .unwrap();
         }
-    }
+    };
 
-    // Focus the project's terminal, then open/reuse a tmux pane running
-    // `claude -r <session>`.
-    let pk = project_key_str.clone();
-    let sid = session_id_str.clone();
+    // Focus the project's terminal, then open/reuse a tmux pane running the
+    // agent's resume command.
+    let session_id = session.header.session.clone();
+    let sid = session_id.clone();
     thread::spawn(move || {
-        let project = {
-            let store = projects::lock();
-            store.by_key(&crate::project::ProjectKey::parse(&pk))
-        };
-        if let Some(project) = project {
-            project
-                .as_project_path()
-                .open(Mutation::Insert, Some(LandIn::TerminalOnly));
-            crate::tmux::resume_claude_session(&project, &sid);
-        }
+        project
+            .as_project_path()
+            .open(Mutation::Insert, Some(LandIn::TerminalOnly));
+        crate::tmux::resume_session(&project, &sid, &command);
     });
 
     Response::builder()
         .header("Content-Type", "application/json")
         .body(Body::from(
             serde_json::json!({
-                "session_id": session_id_str,
-                "project": project_key_str,
+                "session_id": session_id,
+                "project": session.header.project,
+                "agent": agent,
             })
             .to_string(),
         ))
         .unwrap()
 }
 
-fn project_dirs_for_sync() -> Vec<(String, std::path::PathBuf)> {
-    let store = projects::lock();
-    let mut result: Vec<(String, std::path::PathBuf)> = Vec::new();
-    for p in store.all() {
-        let key = p.store_key().to_string();
-        // Workspace file path (for Cursor matching)
-        let filename = format!("{}.code-workspace", key.replace('/', "--"));
-        let gitdir = crate::git::git_common_dir(&p.repo_path);
-        let ws_path = gitdir.join("wormhole/workspaces").join(filename);
-        result.push((key.clone(), ws_path));
-        // A session belongs to the project whose working tree contains its cwd.
-        // For the base project the working tree *is* the repo path; for a task
-        // it is the worktree. The shared repo path is intentionally not matched
-        // for tasks, so a session run in the main checkout maps to the base
-        // project, never to one of its tasks.
-        result.push((key, p.working_tree()));
-    }
-    result
-}
-
 impl QueryParams {
     pub fn from_query(query: Option<&str>) -> Self {
         let mut params = QueryParams {
@@ -588,6 +751,19 @@ impl QueryParams {
             with_editor: false,
             remove: false,
             prune: false,
+            selector: None,
+            pointer: None,
+            json: false,
+            source: None,
+            resolved: false,
+            overwrite: false,
+            missing: None,
+            query: None,
+            until: None,
+            limit: None,
+            tools: false,
+            format: None,
+            redact: false,
         };
         if let Some(query) = query {
             for (key, val) in form_urlencoded::parse(query.as_bytes()) {
@@ -626,6 +802,19 @@ impl QueryParams {
                     "with-editor" => params.with_editor = val == "true" || val == "1",
                     "remove" => params.remove = val == "true" || val == "1",
                     "prune" => params.prune = val == "true" || val == "1",
+                    "where" => params.selector = Some(val.to_string()),
+                    "pointer" => params.pointer = Some(val.to_string()),
+                    "json" => params.json = val == "true" || val == "1",
+                    "source" => params.source = Some(val.to_string()),
+                    "resolved" => params.resolved = val == "true" || val == "1",
+                    "overwrite" => params.overwrite = val == "true" || val == "1",
+                    "missing" => params.missing = Some(val.to_string()),
+                    "q" => params.query = Some(val.to_string()),
+                    "until" => params.until = Some(val.to_string()),
+                    "limit" => params.limit = val.parse().ok(),
+                    "tools" => params.tools = val == "true" || val == "1",
+                    "format" => params.format = Some(val.to_string()),
+                    "redact" => params.redact = val == "true" || val == "1",
                     _ => {}
                 }
             }
@@ -667,7 +856,7 @@ mod tests {
     fn test_parse_land_in() {
         // Grid model: a terminal pin is tmux-only; the editor row is opt-in
         // (only LandIn::Editor creates it).
-        let p = |s: &str| parse_land_in(Some(&s.to_string()));
+        let p = |s: &str| parse_land_in(Some(s));
         assert_eq!(p("terminal"), Some(LandIn::TerminalOnly));
         assert_eq!(p("terminal-only"), Some(LandIn::TerminalOnly));
         assert_eq!(p("editor"), Some(LandIn::Editor));